@@ -0,0 +1,616 @@
+use crate::common::mass::*;
+use crate::ddt::Ddt;
+use crate::fermentation::{Culture, Fermentation, Leavening, Schedule};
+use crate::ingredient::flour::{Allocation, Flours};
+use crate::ingredient::preferment::{self, SolvedPortion};
+use crate::ingredient::YeastRatio;
+use crate::ingredient::{Enrichment, Ingredient, SaltPercentage, Soaker, Yeast};
+use crate::levain::{LevainPlan, LevainStage, Overage};
+use crate::recipe::{Hydration, Recipe};
+use crate::{Error, Result};
+use crate::common::percent::PERCENT;
+use rust_decimal::prelude::*;
+use std::rc::Rc;
+
+/// Constructs a preferment against the recipe's total flour mass, once it is known,
+/// along with the flours its flour is taken from
+pub type PrefermentFactory = Rc<dyn Fn(&Rc<Gram>) -> Result<(Box<dyn Ingredient>, Allocation)>>;
+
+/// Constructs a soaker against the recipe's total flour mass, once it is known
+pub type SoakerFactory = Rc<dyn Fn(&Rc<Gram>) -> Result<Soaker>>;
+
+/// Constructs the yeast against the recipe's total flour mass, once it is known
+pub type YeastFactory = Rc<dyn Fn(&Rc<Gram>) -> Result<Yeast>>;
+
+/// Constructs an enrichment against the recipe's total flour mass, once it is known
+pub type EnrichmentFactory = Rc<dyn Fn(&Rc<Gram>) -> Result<Enrichment<Ratio>>>;
+
+/// How the total flour mass is found
+#[derive(Debug, Clone)]
+enum Scale {
+  /// Given explicitly
+  Mass(Gram),
+  /// Solved so the dough of all pieces weights `Gram` in total
+  Dough(Gram, u32),
+  /// Solved so the named ingredient weights exactly `Gram`
+  Anchor(String, Gram),
+}
+
+/// Builds a `Recipe` from its baker's percentages.
+///
+/// All quantities are relative to the total flour mass, which is either given or solved for
+/// (see `dough` and `anchor`). Nothing is validated until `build`.
+///
+/// # Example
+///
+/// ```
+/// use pn::ingredient::preferment::Starter;
+/// use pn::RecipeBuilder;
+///
+/// let (portion, hydration) = (10.try_into().unwrap(), 100.try_into().unwrap());
+/// let recipe = RecipeBuilder::new(1000)
+///   .hydration(70)
+///   .flour("white", 80)
+///   .flour("rye", 20)
+///   .preferment(move |flour| Starter::new(flour, portion, hydration))
+///   .enrichment("butter", 5)
+///   .build()
+///   .unwrap();
+///
+/// assert_eq!(recipe.water(), 700.into());
+/// ```
+#[derive(Clone)]
+pub struct RecipeBuilder {
+  scale: Scale,
+  hydration: u32,
+  salt: f32,
+  flours: Vec<(String, Result<Ratio>)>,
+  preferments: Vec<PrefermentFactory>,
+  enrichments: Vec<EnrichmentFactory>,
+  water_contents: Vec<(String, Result<Ratio>)>,
+  soakers: Vec<SoakerFactory>,
+  yeast: Option<YeastFactory>,
+  pieces: u32,
+  title: Option<String>,
+  notes: Option<String>,
+  levain: Vec<LevainStage>,
+  levain_overage: f32,
+  temperature: Option<f32>,
+  schedule: Option<Schedule>,
+  ddt: Option<Ddt>,
+}
+
+impl RecipeBuilder {
+  /// A recipe of `total_flour` mass, 70% hydration and 2% salt.
+  /// Unless flours are added, the flour is 100% `White`
+  pub fn new(total_flour: impl Into<Gram>) -> Self {
+    RecipeBuilder {
+      scale: Scale::Mass(total_flour.into()),
+      hydration: 70,
+      salt: 2.0,
+      flours: vec![],
+      preferments: vec![],
+      enrichments: vec![],
+      water_contents: vec![],
+      soakers: vec![],
+      yeast: None,
+      pieces: 1,
+      title: None,
+      notes: None,
+      levain: vec![],
+      levain_overage: 0.0,
+      temperature: None,
+      schedule: None,
+      ddt: None,
+    }
+  }
+
+  /// Liquid as percent of the total flour mass, 50% to 120%
+  pub fn hydration(mut self, hydration: u32) -> Self {
+    self.hydration = hydration;
+    self
+  }
+
+  /// Salt as percent of the total flour mass, 1% to 4%
+  pub fn salt(mut self, salt: f32) -> Self {
+    self.salt = salt;
+    self
+  }
+
+  /// Adds a flour as percent of the total flour mass, all flours should add up to 100%
+  pub fn flour<R>(mut self, name: impl Into<String>, ratio: R) -> Self
+  where
+    R: TryInto<Ratio>,
+    Error: From<R::Error>,
+  {
+    self.flours.push((name.into(), ratio.try_into().map_err(Error::from)));
+    self
+  }
+
+  /// Adds a preferment, `make` constructs it against the recipe's total flour mass.
+  /// The preferment's flour is taken from the flours, in the order they were added
+  pub fn preferment<F, P>(self, make: F) -> Self
+  where
+    F: Fn(&Rc<Gram>) -> P + 'static,
+    P: Ingredient + 'static,
+  {
+    self.preferment_from(make, Allocation::InOrder)
+  }
+
+  /// Adds a preferment whose flour is taken from the flours as directed by `allocation`
+  pub fn preferment_from<F, P>(mut self, make: F, allocation: Allocation) -> Self
+  where
+    F: Fn(&Rc<Gram>) -> P + 'static,
+    P: Ingredient + 'static,
+  {
+    self.preferments.push(Rc::new(move |total_flour| {
+      let preferment: Box<dyn Ingredient> = Box::new(make(total_flour));
+      Ok((preferment, allocation.clone()))
+    }));
+    self
+  }
+
+  /// Adds a preferment by its textual description e.g. `starter:10:100` or `starter:10:100@rye`
+  pub fn preferment_desc(mut self, desc: impl Into<String>) -> Self {
+    let desc = desc.into();
+    self.preferments.push(Rc::new(move |total_flour| preferment::BUILDER.allocated(&desc, total_flour)));
+    self
+  }
+
+  /// Adds an enrichment as percent of the total flour mass
+  pub fn enrichment<R>(mut self, name: impl Into<String>, ratio: R) -> Self
+  where
+    R: TryInto<Ratio>,
+    Error: From<R::Error>,
+  {
+    let (name, ratio) = (name.into(), ratio.try_into().map_err(Error::from));
+    self.enrichments.push(Rc::new(move |total_flour| Ok(Enrichment::new(name.clone(), total_flour, ratio.clone()?))));
+    self
+  }
+
+  /// Adds an enrichment by its mass, regardless of the total flour mass
+  pub fn enrichment_mass(mut self, name: impl Into<String>, mass: impl Into<Gram>) -> Self {
+    let (name, mass) = (name.into(), mass.into());
+    self.enrichments.push(Rc::new(move |total_flour| Enrichment::new_by_mass(name.clone(), total_flour, mass)));
+    self
+  }
+
+  /// Adds an enrichment by its textual description e.g. `butter%5` or `milk:200:87`
+  pub fn enrichment_desc(mut self, desc: impl Into<String>) -> Self {
+    let desc = desc.into();
+    self.enrichments.push(Rc::new(move |total_flour| Enrichment::parse(&desc, total_flour)));
+    self
+  }
+
+  /// Sets the water in the named enrichment as percent of its mass, overriding the built in content
+  /// (e.g. 87% for milk, 16% for butter). The water counts toward the hydration
+  pub fn water_content<R>(mut self, name: impl Into<String>, water: R) -> Self
+  where
+    R: TryInto<Ratio>,
+    Error: From<R::Error>,
+  {
+    self.water_contents.push((name.into(), water.try_into().map_err(Error::from)));
+    self
+  }
+
+  /// Adds a soaker, `make` constructs it against the recipe's total flour mass.
+  /// The water it holds is part of the dough but doesn't count toward the (effective) hydration
+  pub fn soaker<F>(mut self, make: F) -> Self
+  where
+    F: Fn(&Rc<Gram>) -> Soaker + 'static,
+  {
+    self.soakers.push(Rc::new(move |total_flour| Ok(make(total_flour))));
+    self
+  }
+
+  /// Adds a soaker by its textual description e.g. `flax:10:100` or `oats:20:300:200`
+  pub fn soaker_desc(mut self, desc: impl Into<String>) -> Self {
+    let desc = desc.into();
+    self.soakers.push(Rc::new(move |total_flour| Soaker::parse(&desc, total_flour)));
+    self
+  }
+
+  /// Sets the commercial yeast, `make` constructs it against the recipe's total flour mass.
+  /// The yeast in preferments is taken off it
+  pub fn yeast<F>(mut self, make: F) -> Self
+  where
+    F: Fn(&Rc<Gram>) -> Yeast + 'static,
+  {
+    self.yeast = Some(Rc::new(move |total_flour| Ok(make(total_flour))));
+    self
+  }
+
+  /// Sets the commercial yeast by its textual description e.g. `instant:0.4` or `instant:0.4@fresh`
+  pub fn yeast_desc(mut self, desc: impl Into<String>) -> Self {
+    let desc = desc.into();
+    self.yeast = Some(Rc::new(move |total_flour| Yeast::parse(&desc, total_flour)));
+    self
+  }
+
+  /// Divides the dough to `pieces`
+  pub fn pieces(mut self, pieces: u32) -> Self {
+    self.pieces = pieces;
+    self
+  }
+
+  /// Solves for the total flour mass that yields `pieces` of dough, each weighting `weight`
+  pub fn dough(mut self, weight: impl Into<Gram>, pieces: u32) -> Self {
+    self.scale = Scale::Dough(weight.into(), pieces);
+    self.pieces = pieces;
+    self
+  }
+
+  /// Solves for the total flour mass at which the named ingredient weights exactly `mass`
+  pub fn anchor(mut self, name: impl Into<String>, mass: impl Into<Gram>) -> Self {
+    self.scale = Scale::Anchor(name.into(), mass.into());
+    self
+  }
+
+  pub fn title(mut self, title: impl Into<String>) -> Self {
+    self.title = Some(title.into());
+    self
+  }
+
+  pub fn notes(mut self, notes: impl Into<String>) -> Self {
+    self.notes = Some(notes.into());
+    self
+  }
+
+  /// Adds a stage to the build of the starter, from the seed culture to the final levain
+  pub fn levain_stage(mut self, stage: LevainStage) -> Self {
+    self.levain.push(stage);
+    self
+  }
+
+  /// Builds that much more levain than the recipe needs, as percent of it, 0% to 50%
+  pub fn levain_overage(mut self, overage: f32) -> Self {
+    self.levain_overage = overage;
+    self
+  }
+
+  /// The dough temperature in °C the fermentation is estimated at, 15°C to 32°C.
+  /// Unless given, the desired dough temperature, or 24°C
+  pub fn temperature(mut self, temperature: f32) -> Self {
+    self.temperature = Some(temperature);
+    self
+  }
+
+  /// Tempers the water added to reach the desired dough temperature
+  pub fn ddt(mut self, ddt: Ddt) -> Self {
+    self.ddt = Some(ddt);
+    self
+  }
+
+  /// The temperature the fermentation is estimated at. None when it would be a desired dough temperature the
+  /// estimate doesn't hold for, the water is still tempered to reach it
+  fn dough_temperature(&self) -> Option<f32> {
+    match (self.temperature, self.ddt) {
+      (Some(temperature), _) => Some(temperature),
+      (None, Some(ddt)) => Fermentation::TEMPERATURES.contains(&ddt.target).then_some(ddt.target),
+      (None, None) => Some(Fermentation::REFERENCE_TEMPERATURE),
+    }
+  }
+
+  /// Solves the starter's portion, or the yeast when there is no starter, so the bulk ferments on `schedule`
+  /// at the dough temperature
+  pub fn schedule(mut self, schedule: Schedule) -> Self {
+    self.schedule = Some(schedule);
+    self
+  }
+
+  /// The builder whose first starter, or yeast when there is no starter, ferments the bulk on `schedule`.
+  /// The inoculation is linear in the total flour, so it is solved on a sample recipe
+  fn scheduled(&self, schedule: Schedule) -> Result<RecipeBuilder> {
+    let mut builder = RecipeBuilder { schedule: None, ..self.clone() };
+    let sample = builder.assemble(1000.into())?;
+    let (total_flour, bulk) = (sample.total_mass.clone(), schedule.bulk());
+    // Fermenting on schedule needs the estimate, so a desired dough temperature out of its range is an error
+    let temperature = self.dough_temperature().or(self.ddt.map(|ddt| ddt.target)).unwrap_or_default();
+    let percent = |mass: Gram| (mass.0 * PERCENT / total_flour.0).to_f32().unwrap_or_default();
+    let out_of_range = |what: &str, percent: f32, range: std::ops::RangeInclusive<u32>| {
+      Error::UnreachableSchedule(bulk, what.to_owned(), percent as f64, *range.start(), *range.end())
+    };
+
+    let starter = self.preferments.iter().position(|make| {
+      matches!(make(&total_flour).map(|(p, _)| p.leavening()), Ok(Some(Leavening::Sourdough(_))))
+    });
+    if let Some(index) = starter {
+      let solved = Fermentation::inoculation(&sample, temperature, bulk, Culture::Starter)? as f32;
+      let portion =
+        SolvedPortion::checked(solved).map_err(|_| out_of_range("starter", solved, SolvedPortion::range()))?;
+      // The starter as described, only its portion solved
+      let make = self.preferments[index].clone();
+      builder.preferments[index] = Rc::new(move |total_flour| {
+        let (starter, allocation) = make(total_flour)?;
+        Ok((starter.with_portion(portion.into()).ok_or(Error::NothingToSchedule)?, allocation))
+      });
+    } else if let Some(make) = self.yeast.clone() {
+      // The yeast in preferments is taken off the yeast added, so the ratio includes it
+      let added = Fermentation::inoculation(&sample, temperature, bulk, Culture::Yeast)? as f32;
+      let carried = sample.ingredients().iter().fold(Gram::ZERO, |a, i| a + i.carries("yeast"));
+      let kind = make(&total_flour)?.kind();
+      let solved = (added + percent(carried)) * kind.per_instant().to_f32().unwrap_or(1.0);
+      let what = format!("{} yeast", kind);
+      let ratio = YeastRatio::checked(solved).map_err(|_| out_of_range(&what, solved, YeastRatio::range()))?;
+      builder.yeast = Some(Rc::new(move |total_flour| Ok(make(total_flour)?.with_ratio(ratio))));
+    } else {
+      return Err(Error::NothingToSchedule);
+    }
+    Ok(builder)
+  }
+
+  /// Plans the build of the recipe's starter
+  fn plan_levain(&self, recipe: &Recipe) -> Result<LevainPlan> {
+    let overage = Overage::checked(self.levain_overage)?;
+    let starter = recipe
+      .ingredients()
+      .iter()
+      .find(|i| i.mass_of("starter").is_some())
+      .ok_or_else(|| Error::UnknownIngredient("starter".to_owned()))?;
+    let hydration = starter.water().as_ratio_of(&starter.flour());
+    Ok(LevainPlan::new(&self.levain, starter.total() + starter.kept(), hydration, overage))
+  }
+
+  /// Builds the recipe with the given total flour mass
+  fn assemble(&self, mass: Gram) -> Result<Recipe> {
+    let hydration = Hydration::checked(self.hydration as f32)?;
+    let salt = SaltPercentage::checked(self.salt)?;
+
+    let mut recipe = Recipe::new(mass, hydration);
+    recipe.title = self.title.clone();
+    recipe.notes = self.notes.clone();
+    recipe.pieces = self.pieces;
+    recipe.ddt = self.ddt;
+
+    let flours = self
+      .flours
+      .iter()
+      .map(|(name, ratio)| Ok((name.clone(), ratio.clone()?)))
+      .collect::<Result<Vec<(String, Ratio)>>>()?;
+    let ratios: Ratio = flours.iter().map(|(_, ratio)| *ratio).sum();
+    if !flours.is_empty() && ratios != Ratio::try_from(100)? {
+      return Err(Error::InsufficientFLourRatios(ratios));
+    }
+
+    let flours = if flours.is_empty() {
+      Flours::new(&recipe.total_mass).add_flour("White".to_owned(), Ratio::try_from(100)?)
+    } else {
+      flours
+        .iter()
+        .fold(Flours::new(&recipe.total_mass), |fs, (name, ratio)| fs.add_flour(name.clone(), *ratio))
+    };
+
+    let flours = self.preferments.iter().try_fold(flours, |fs, make| {
+      let (preferment, allocation) = make(&recipe.total_mass)?;
+      let fs = fs.repurpose_with(&*preferment, &allocation)?;
+      recipe.ingredients.push(preferment);
+      Ok::<_, Error>(fs)
+    })?;
+    recipe.ingredients.push(Box::new(flours));
+    for make in &self.soakers {
+      recipe.ingredients.push(Box::new(make(&recipe.total_mass)?));
+    }
+
+    // Enrichments already in the preferments are not added again
+    let carried = |name: &str| recipe.ingredients.iter().fold(Gram::ZERO, |a, i| a + i.carries(name));
+
+    let mut enrichments: Vec<Box<dyn Ingredient>> = vec![];
+    if let Some(make) = &self.yeast {
+      enrichments.push(Box::new(make(&recipe.total_mass)?.carried(carried("yeast"))));
+    }
+
+    let salt = Enrichment::<SaltPercentage>::new("SALT".to_owned(), &recipe.total_mass, salt).carrying(carried("salt"))?;
+    enrichments.push(Box::new(salt));
+    for make in &self.enrichments {
+      let enrichment = make(&recipe.total_mass)?;
+      let in_preferments = carried(&enrichment.name);
+      let enrichment = match self.water_contents.iter().rev().find(|(name, _)| name.eq_ignore_ascii_case(&enrichment.name)) {
+        Some((_, water)) => enrichment.water_content(water.clone()?),
+        None => enrichment,
+      };
+      enrichments.push(Box::new(enrichment.carrying(in_preferments)?));
+    }
+    recipe.ingredients.extend(enrichments);
+
+    Ok(recipe.add_missing_water())
+  }
+
+  /// Solves for the total flour mass at which `measure` of the recipe equals `target`
+  ///
+  /// The recipe's quantities are linear in the total flour mass
+  ///
+  /// ```text
+  ///         measure(mass) = a x mass + b
+  /// ```
+  ///
+  /// So two sample recipes are enough to find `a` and `b`, a third one corrects for the rounding of
+  /// the individual ingredients
+  fn solve_mass(&self, measure: impl Fn(&Recipe) -> Gram, target: Gram) -> Result<Gram> {
+    let sample: Gram = 1000.into();
+    let low = measure(&self.assemble(sample)?);
+    let high = measure(&self.assemble(sample * 2)?);
+
+    let a = (high - low).0 / sample.0;
+    let b = low - sample * a;
+    if a <= Decimal::ZERO || target <= b {
+      return Err(Error::UnreachableTarget(target));
+    }
+    let mass = Gram(((target - b).0 / a).round_dp(2));
+    if mass <= Gram::ZERO {
+      return Err(Error::UnreachableTarget(target));
+    }
+    let error = measure(&self.assemble(mass)?) - target;
+    Ok(Gram((mass.0 - error.0 / a).round_dp(2)))
+  }
+
+  /// Builds the recipe, solving for the total flour mass when a dough weight or an anchor is requested,
+  /// planning the levain build when its stages are given and estimating the fermentation
+  pub fn build(&self) -> Result<Recipe> {
+    if let Some(schedule) = self.schedule {
+      let mut recipe = self.scheduled(schedule)?.build()?;
+      if let Some(fermentation) = &mut recipe.fermentation {
+        fermentation.schedule = Some(schedule);
+      }
+      return Ok(recipe);
+    }
+    if self.pieces == 0 {
+      return Err(Error::InvalidPieces(self.pieces));
+    }
+
+    let mass = match &self.scale {
+      Scale::Mass(mass) => *mass,
+      Scale::Dough(weight, pieces) => self.solve_mass(Recipe::total, Gram(weight.0 * Decimal::from(*pieces)))?,
+      Scale::Anchor(name, target) => {
+        if self.assemble(1000.into())?.mass_of(name).is_none() {
+          return Err(Error::UnknownIngredient(name.clone()));
+        }
+        self.solve_mass(|r| r.mass_of(name).unwrap_or(Gram::ZERO), *target)?
+      }
+    };
+    let mut recipe = self.assemble(mass)?;
+    if !self.levain.is_empty() {
+      recipe.levain = Some(self.plan_levain(&recipe)?);
+    }
+    recipe.fermentation = match self.dough_temperature() {
+      Some(temperature) => Fermentation::estimate(&recipe, temperature)?,
+      None => None,
+    };
+    Ok(recipe)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ingredient::preferment::Starter;
+  use crate::ingredient::YeastType;
+
+  fn builder() -> RecipeBuilder {
+    RecipeBuilder::new(1000)
+      .flour("white", 80)
+      .flour("rye", 20)
+      .preferment(|flour| Starter::new(flour, 10.try_into().unwrap(), 100.try_into().unwrap()))
+      .enrichment("butter", 5)
+  }
+
+  #[test]
+  fn typed_and_described_preferments_are_the_same() {
+    let typed = builder().build().unwrap();
+    let described = RecipeBuilder::new(1000)
+      .flour("white", 80)
+      .flour("rye", 20)
+      .preferment_desc("starter:10:100")
+      .enrichment("butter", 5)
+      .build()
+      .unwrap();
+
+    assert_eq!(typed.total(), described.total());
+    assert_eq!(typed.water(), described.water());
+    assert_eq!(typed.mass_of("starter"), Some(200.into()));
+  }
+
+  #[test]
+  fn preferment_flour_is_taken_from_the_named_flour() {
+    let recipe = RecipeBuilder::new(1000)
+      .flour("white", 80)
+      .flour("rye", 20)
+      .preferment_desc("starter:20:100@rye")
+      .build()
+      .unwrap();
+    let flours = recipe.ingredients().iter().find_map(|i| i.mass_of("white").map(|_| i.flour()));
+    assert_eq!(flours, Some(800.into()));
+
+    let too_much = builder().preferment_desc("starter:25:100@rye").build().unwrap_err();
+    assert_eq!(too_much, Error::InsufficientNamedFlour("rye".to_owned(), 200.into(), 250.into()));
+  }
+
+  #[test]
+  fn levain_is_planned_for_the_starter() {
+    let recipe = builder()
+      .levain_stage(LevainStage::new(1.0, 2.0, 2.0))
+      .levain_stage(LevainStage::new(1.0, 5.0, 5.0))
+      .build()
+      .unwrap();
+    let plan = recipe.levain.unwrap();
+    assert_eq!(plan.levain, 200.into());
+    assert_eq!(plan.stages[1].total, 200.into());
+
+    let kept = RecipeBuilder::new(1000)
+      .flour("white", 100)
+      .preferment_desc("starter:10:100:50")
+      .levain_stage(LevainStage::new(1.0, 5.0, 5.0))
+      .build()
+      .unwrap();
+    assert_eq!(kept.total(), 1720.into());
+    assert_eq!(kept.levain.unwrap().levain, 250.into());
+
+    let no_starter = RecipeBuilder::new(1000).levain_stage(LevainStage::new(1.0, 2.0, 2.0)).build();
+    assert_eq!(no_starter.unwrap_err(), Error::UnknownIngredient("starter".to_owned()));
+  }
+
+  #[test]
+  fn soaked_water_does_not_hydrate_the_flour() {
+    let recipe = builder().soaker_desc("flax:10:100").build().unwrap();
+    let properties = recipe.properties();
+    assert_eq!(recipe.bound_water(), 100.into());
+    assert_eq!(properties.hydration.as_decimal(), properties.expected_hydration.as_decimal());
+    assert_eq!(properties.total_hydration, 80.try_into().unwrap());
+    assert_eq!(properties.total_water, 800.into());
+  }
+
+  #[test]
+  fn enrichments_water_counts_toward_hydration() {
+    let recipe = builder().enrichment_mass("milk", 200).build().unwrap();
+    assert_eq!(recipe.mass_of("water"), Some(418.into()));
+    assert_eq!(recipe.water(), 700.into());
+
+    let recipe = builder().enrichment_mass("milk", 200).water_content("milk", 90).build().unwrap();
+    assert_eq!(recipe.mass_of("water"), Some(412.into()));
+  }
+
+  #[test]
+  fn yeast_in_preferments_is_not_added_again() {
+    let recipe = RecipeBuilder::new(1000).yeast_desc("instant:0.4").build().unwrap();
+    assert_eq!(recipe.mass_of("yeast"), Some(4.into()));
+
+    let recipe = RecipeBuilder::new(1000)
+      .preferment_desc("poolish:30:100:0.1")
+      .yeast(|flour| Yeast::new(flour, YeastType::Instant, 0.4.try_into().unwrap()).used_as(YeastType::Fresh))
+      .build()
+      .unwrap();
+    assert_eq!(recipe.mass_of("yeast"), Some(Gram::from(11.1)));
+  }
+
+  #[test]
+  fn water_is_tempered_to_the_desired_dough_temperature() {
+    let recipe = builder().ddt(Ddt::new(26.0, 20.0, 24.0, 2.0).preferment(24.0)).build().unwrap();
+    let tempering = recipe.tempering.clone().unwrap();
+    // (26 x 4) - (20 + 24 + 24 + 2)
+    assert_eq!(tempering.temperature, 34.0);
+    assert_eq!(Some(tempering.water), recipe.mass_of("water"));
+    assert_eq!(recipe.fermentation.unwrap().temperature, 26.0);
+
+    let recipe = RecipeBuilder::new(1000).ddt(Ddt::new(24.0, 26.0, 30.0, 14.0).tap(15.0)).build().unwrap();
+    let tempering = recipe.report().water_temperature.unwrap();
+    assert_eq!(tempering.ice + tempering.water, 700.into());
+    assert_eq!(tempering.tap, Some(15.0));
+  }
+
+  #[test]
+  fn desired_dough_temperature_out_of_the_estimate_still_tempers_the_water() {
+    let recipe = builder().ddt(Ddt::new(33.0, 20.0, 22.0, 2.0)).build().unwrap();
+    assert!(recipe.tempering.is_some());
+    assert!(recipe.fermentation.is_none());
+  }
+
+  #[test]
+  fn out_of_range_percentages_are_errors() {
+    assert_eq!(builder().hydration(200).build().unwrap_err(), Error::InvalidPercentage(200, 50, 120));
+    assert!(builder().salt(10.0).build().is_err());
+    assert!(builder().preferment_desc("starter:10").build().is_err());
+    assert_eq!(
+      builder().flour("spelt", 10).build().unwrap_err(),
+      Error::InsufficientFLourRatios(110.try_into().unwrap())
+    );
+  }
+}
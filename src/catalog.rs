@@ -0,0 +1,276 @@
+use crate::common::mass::Ratio;
+use crate::{Error, Result};
+use lazy_static::lazy_static;
+use prettytable::{format, row, Table};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  path::Path,
+  sync::{Arc, RwLock},
+};
+
+/// The catalog shipped with pn, see `src/catalog.toml`
+const BUILT_IN: &str = include_str!("catalog.toml");
+
+/// Whether an ingredient is a flour or anything else added to the dough
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+  Flour,
+  #[default]
+  Ingredient,
+}
+
+/// What a known ingredient is made of, as percent of its mass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+  pub name: String,
+
+  /// Other names it goes by, e.g. `wholemeal` for `wholewheat`
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub aliases: Vec<String>,
+
+  #[serde(default)]
+  pub kind: Kind,
+
+  #[serde(default)]
+  pub water: f32,
+
+  /// Whether its water counts toward the dough's hydration, as for milk, eggs or butter.
+  /// The moisture of dry ingredients, e.g. flour or seeds, doesn't
+  #[serde(default)]
+  pub hydrates: bool,
+
+  #[serde(default)]
+  pub fat: f32,
+
+  #[serde(default)]
+  pub sugar: f32,
+
+  #[serde(default)]
+  pub protein: f32,
+
+  #[serde(default)]
+  pub fibre: f32,
+
+  #[serde(default)]
+  pub salt: f32,
+
+  /// In gram per millilitre
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub density: Option<f32>,
+
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub allergens: Vec<String>,
+}
+
+/// Catalog entries as written in a catalog file
+///
+/// ```toml
+/// [[ingredient]]
+/// name = "tahini"
+/// aliases = ["sesame paste"]
+/// water = 3
+/// fat = 54
+/// protein = 17
+/// fibre = 9
+/// allergens = ["sesame"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entries {
+  #[serde(default)]
+  pub ingredient: Vec<Entry>,
+}
+
+/// The ingredients pn knows about, the built in ones and those added from local catalog files
+pub struct Catalog {
+  entries: RwLock<Vec<Arc<Entry>>>,
+}
+
+impl Entry {
+  fn names(&self) -> impl Iterator<Item = &String> {
+    std::iter::once(&self.name).chain(self.aliases.iter())
+  }
+
+  fn is_named(&self, name: &str) -> bool {
+    self.names().any(|known| known.eq_ignore_ascii_case(name.trim()))
+  }
+
+  fn validate(&self) -> Result<()> {
+    let invalid = |message: String| Error::InvalidConfigFile(format!("ingredient '{}' {}", self.name, message));
+    if self.name.trim().is_empty() {
+      return Err(invalid("has no name".to_owned()));
+    }
+
+    let parts = [
+      ("water", self.water),
+      ("fat", self.fat),
+      ("sugar", self.sugar),
+      ("protein", self.protein),
+      ("fibre", self.fibre),
+      ("salt", self.salt),
+    ];
+    if let Some((part, value)) = parts.iter().find(|(_, value)| !(0.0..=100.0).contains(value)) {
+      return Err(invalid(format!("{} {}% is out of range, 0% to 100%", part, value)));
+    }
+    let total: f32 = parts.iter().map(|(_, value)| value).sum();
+    if total > 100.0 {
+      return Err(invalid(format!("is made of {}%, more than 100%", total)));
+    }
+    match self.density {
+      Some(density) if density <= 0.0 => Err(invalid(format!("density {} is not positive", density))),
+      _ => Ok(()),
+    }
+  }
+
+  /// The water in it counting toward the hydration, percent of its mass
+  pub fn water_content(&self) -> Option<Ratio> {
+    self.hydrates.then_some(self.water).and_then(|water| Ratio::checked(water).ok())
+  }
+}
+
+impl Entries {
+  pub fn from_toml(content: &str) -> Result<Self> {
+    let entries: Entries = toml::from_str(content).map_err(|e| Error::InvalidConfigFile(e.to_string()))?;
+    for entry in &entries.ingredient {
+      entry.validate()?;
+    }
+    Ok(entries)
+  }
+
+  pub fn load(path: &Path) -> Result<Self> {
+    let content =
+      fs::read_to_string(path).map_err(|e| Error::ConfigFileAccess(path.display().to_string(), e.to_string()))?;
+    Self::from_toml(&content)
+  }
+}
+
+impl Catalog {
+  fn new(entries: Entries) -> Self {
+    Catalog {
+      entries: RwLock::new(entries.ingredient.into_iter().map(Arc::new).collect()),
+    }
+  }
+
+  /// Adds the `entries` to the catalog, an entry replaces the known ingredient it shares a name or an alias with
+  pub fn register(&self, entries: Entries) {
+    let mut known = self.entries.write().unwrap_or_else(|e| e.into_inner());
+    for entry in entries.ingredient {
+      known.retain(|k| !entry.names().any(|name| k.is_named(name)));
+      known.push(Arc::new(entry));
+    }
+  }
+
+  /// The ingredient known by `name` or by one of its aliases, case insensitively
+  pub fn get(&self, name: &str) -> Option<Arc<Entry>> {
+    let known = self.entries.read().unwrap_or_else(|e| e.into_inner());
+    known.iter().find(|entry| entry.is_named(name)).cloned()
+  }
+
+  /// All the known ingredients, flours first, sorted by name
+  pub fn entries(&self) -> Vec<Arc<Entry>> {
+    let mut entries = self.entries.read().unwrap_or_else(|e| e.into_inner()).clone();
+    entries.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+    entries
+  }
+
+  /// The allergens of the named ingredients, sorted
+  pub fn allergens<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut allergens: Vec<String> = names
+      .into_iter()
+      .filter_map(|name| self.get(name))
+      .flat_map(|entry| entry.allergens.clone())
+      .collect();
+    allergens.sort();
+    allergens.dedup();
+    allergens
+  }
+
+  pub fn display_json(&self) -> Result<()> {
+    let entries: Vec<Entry> = self.entries().iter().map(|entry| Entry::clone(entry)).collect();
+    let json = serde_json::to_string_pretty(&entries).map_err(|e| Error::Serialization(e.to_string()))?;
+    println!("{}", json);
+    Ok(())
+  }
+
+  pub fn display(&self) -> Result<()> {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.add_row(row![cbFy => "name", "water", "fat", "sugar", "protein", "fibre", "salt", "density", "allergens", "aliases"]);
+    let percent = |value: f32| if value > 0.0 { format!("{}%", value) } else { String::new() };
+    for entry in self.entries() {
+      let name = match entry.kind {
+        Kind::Flour => format!("{} (flour)", entry.name),
+        Kind::Ingredient => entry.name.clone(),
+      };
+      table.add_row(row![
+        b -> name,
+        r -> if entry.hydrates { format!("{}*", percent(entry.water)) } else { percent(entry.water) },
+        r -> percent(entry.fat),
+        r -> percent(entry.sugar),
+        r -> percent(entry.protein),
+        r -> percent(entry.fibre),
+        r -> percent(entry.salt),
+        r -> entry.density.map(|d| format!("{} g/ml", d)).unwrap_or_default(),
+        entry.allergens.join(", "),
+        entry.aliases.join(", ")
+      ]);
+    }
+    table.printstd();
+    println!("* Counts toward the dough's hydration");
+    Ok(())
+  }
+}
+
+lazy_static! {
+  pub static ref CATALOG: Catalog = Catalog::new(Entries::from_toml(BUILT_IN).expect("the built in catalog is valid"));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn built_in_catalog_is_valid() {
+    let entries = Entries::from_toml(BUILT_IN).unwrap();
+    for entry in &entries.ingredient {
+      for name in entry.names() {
+        let owners = entries.ingredient.iter().filter(|e| e.is_named(name)).count();
+        assert_eq!(owners, 1, "'{}' names more than one ingredient", name);
+      }
+    }
+  }
+
+  #[test]
+  fn names_and_aliases_are_resolved_case_insensitively() {
+    assert_eq!(CATALOG.get("Milk").unwrap().water, 87.0);
+    assert_eq!(CATALOG.get("WHOLEMEAL").unwrap().name, "wholewheat");
+    assert_eq!(CATALOG.get("white").unwrap().kind, Kind::Flour);
+    assert!(CATALOG.get("unobtainium").is_none());
+    assert_eq!(CATALOG.allergens(["White", "rye", "butter", "salt"]), vec!["gluten", "milk", "wheat"]);
+  }
+
+  #[test]
+  fn local_entries_are_added() {
+    let entries = Entries::from_toml(
+      r#"
+      [[ingredient]]
+      name = "tahini"
+      aliases = ["sesame paste"]
+      water = 3
+      fat = 54
+      allergens = ["sesame"]
+      "#,
+    )
+    .unwrap();
+    let catalog = Catalog::new(Entries::from_toml(BUILT_IN).unwrap());
+    catalog.register(entries);
+    assert_eq!(catalog.get("Sesame Paste").unwrap().fat, 54.0);
+    assert!(catalog.get("milk").is_some());
+
+    let invalid = Entries::from_toml("[[ingredient]]\nname = \"x\"\nwater = 80\nfat = 30");
+    assert!(matches!(invalid, Err(Error::InvalidConfigFile(_))));
+  }
+}
@@ -0,0 +1,303 @@
+use rust_decimal::Decimal;
+use std::{ops::Range, str::FromStr};
+
+use crate::common::{percent::PERCENT, Gram, Percent};
+use crate::error::{Diagnostic, Error, Result};
+
+/// A user supplied description of a recipe's ingredient or property, e.g. `rye:20` or `starter:10:100`.
+///
+/// A description is taken apart into `Field`s, each remembering where it is in the description
+/// so any problem with it is reported pointing at it, along with the expected syntax.
+#[derive(Debug, Clone)]
+pub struct Description {
+  /// What is described, e.g. `flour`
+  kind: String,
+
+  text: String,
+
+  /// The expected syntax, e.g. `<name>:<ratio>`
+  syntax: String,
+
+  /// A valid description, e.g. `rye:20`
+  example: String,
+}
+
+/// A number a `Field` is parsed as, finite ones only as `NaN` or `inf` make no quantity
+pub trait Number: FromStr {
+  fn is_finite(&self) -> bool;
+}
+
+impl Number for f32 {
+  fn is_finite(&self) -> bool {
+    f32::is_finite(*self)
+  }
+}
+
+impl Number for u32 {
+  fn is_finite(&self) -> bool {
+    true
+  }
+}
+
+impl Number for Decimal {
+  fn is_finite(&self) -> bool {
+    true
+  }
+}
+
+/// A part of a `Description`
+#[derive(Debug, Clone)]
+pub struct Field<'a> {
+  description: &'a Description,
+  name: &'static str,
+  span: Range<usize>,
+}
+
+impl Description {
+  pub fn new(kind: &str, text: &str, syntax: &str, example: &str) -> Self {
+    Description {
+      kind: kind.to_owned(),
+      text: text.to_owned(),
+      syntax: syntax.to_owned(),
+      example: example.to_owned(),
+    }
+  }
+
+  /// The same text described more specifically, i.e. once a preferment is known to be a starter
+  pub fn specialize(&self, kind: &str, syntax: &str, example: &str) -> Self {
+    Description::new(kind, &self.text, syntax, example)
+  }
+
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  /// The whole description as a single field
+  pub fn whole(&self, name: &'static str) -> Field<'_> {
+    Field {
+      description: self,
+      name,
+      span: 0..self.text.len(),
+    }
+  }
+
+  pub fn help(&self) -> String {
+    format!("{} syntax is {}, e.g. {}", self.kind, self.syntax, self.example)
+  }
+}
+
+impl<'a> Field<'a> {
+  pub fn text(&self) -> &'a str {
+    &self.description.text[self.span.clone()]
+  }
+
+  pub fn span(&self) -> Range<usize> {
+    self.span.clone()
+  }
+
+  pub fn description(&self) -> &'a Description {
+    self.description
+  }
+
+  /// The same field of a more specific description of the same text
+  pub fn rebase<'b>(&self, description: &'b Description) -> Field<'b> {
+    Field {
+      description,
+      name: self.name,
+      span: self.span.clone(),
+    }
+  }
+
+  fn sub(&self, name: &'static str, span: Range<usize>) -> Field<'a> {
+    Field {
+      description: self.description,
+      name,
+      span,
+    }
+  }
+
+  /// An error pointing at this field
+  pub fn error(&self, message: String) -> Error {
+    self.error_with_help(message, self.description.help())
+  }
+
+  pub fn error_with_help(&self, message: String, help: String) -> Error {
+    Error::InvalidArguments(vec![Diagnostic {
+      kind: self.description.kind.clone(),
+      text: self.description.text.clone(),
+      span: self.span.clone(),
+      field: self.name.to_owned(),
+      message,
+      help,
+    }])
+  }
+
+  /// Splits the field at the first `separator`
+  pub fn split_once(&self, separator: char, first: &'static str, second: &'static str) -> Result<(Field<'a>, Field<'a>)> {
+    match self.text().find(separator) {
+      Some(at) => {
+        let at = self.span.start + at;
+        Ok((
+          self.sub(first, self.span.start..at),
+          self.sub(second, at + separator.len_utf8()..self.span.end),
+        ))
+      }
+      None => Err(self.error(format!("missing '{}' separating <{}> and <{}>", separator, first, second))),
+    }
+  }
+
+  /// Splits the field at the last `separator`
+  pub fn rsplit_once(&self, separator: char, first: &'static str, second: &'static str) -> Result<(Field<'a>, Field<'a>)> {
+    match self.text().rfind(separator) {
+      Some(at) => {
+        let at = self.span.start + at;
+        Ok((
+          self.sub(first, self.span.start..at),
+          self.sub(second, at + separator.len_utf8()..self.span.end),
+        ))
+      }
+      None => Err(self.error(format!("missing '{}' separating <{}> and <{}>", separator, first, second))),
+    }
+  }
+
+  /// Splits the field at the first `separator`, the `rest` is empty when there is no separator
+  pub fn split_first(&self, separator: char, first: &'static str, rest: &'static str) -> (Field<'a>, Field<'a>) {
+    self
+      .split_once(separator, first, rest)
+      .unwrap_or_else(|_| (self.sub(first, self.span.clone()), self.sub(rest, self.span.end..self.span.end)))
+  }
+
+  /// Splits the field at every `separator`, all parts named `name`
+  pub fn split_all(&self, separator: char, name: &'static str) -> Vec<Field<'a>> {
+    let mut start = self.span.start;
+    let mut parts = vec![];
+    for part in self.text().split(separator) {
+      parts.push(self.sub(name, start..start + part.len()));
+      start += part.len() + separator.len_utf8();
+    }
+    parts
+  }
+
+  /// Splits the field at every `separator`, expecting at least `required` and at most `names.len()` parts
+  pub fn split(&self, separator: char, names: &[&'static str], required: usize) -> Result<Vec<Field<'a>>> {
+    let parts: Vec<Range<usize>> = if self.text().is_empty() {
+      vec![]
+    } else {
+      self.split_all(separator, self.name).into_iter().map(|f| f.span).collect()
+    };
+    if parts.len() < required || parts.len() > names.len() {
+      let expected = if required == names.len() {
+        format!("{}", required)
+      } else {
+        format!("{} to {}", required, names.len())
+      };
+      return Err(self.error(format!("expected {} parts, got {}", expected, parts.len())));
+    }
+    Ok(parts.into_iter().zip(names).map(|(span, name)| self.sub(name, span)).collect())
+  }
+
+  /// A non empty name
+  pub fn name(&self) -> Result<String> {
+    match self.text().trim() {
+      "" => Err(self.error("is empty".to_owned())),
+      name => Ok(name.to_owned()),
+    }
+  }
+
+  /// A finite number
+  pub fn number<T: Number>(&self) -> Result<T> {
+    match self.text().trim().parse::<T>() {
+      Ok(value) if value.is_finite() => Ok(value),
+      _ => Err(self.error(format!("'{}' is not a number", self.text()))),
+    }
+  }
+
+  /// A positive mass in gram, e.g. `150` or `150g`
+  pub fn mass(&self) -> Result<Gram> {
+    let text = self.text().trim();
+    let mass = text
+      .strip_suffix('g')
+      .unwrap_or(text)
+      .trim()
+      .parse::<Decimal>()
+      .map_err(|_| self.error(format!("'{}' is not a mass", self.text())))?;
+    match mass {
+      mass if mass > Decimal::ZERO => Ok(Gram(mass)),
+      mass => Err(self.error(format!("{}g is not a positive mass", mass))),
+    }
+  }
+
+  /// A percentage within the bounds of `Percent<MIN, MAX, DECIMALS>`
+  pub fn percent<const MIN: usize, const MAX: usize, const DECIMALS: usize>(
+    &self,
+  ) -> Result<Percent<MIN, MAX, DECIMALS>> {
+    let value = self.number::<f32>()?;
+    if value < MIN as f32 || value > MAX as f32 {
+      return Err(self.error(format!(
+        "{}% is out of range, {} is {}% to {}%",
+        value, self.name, MIN, MAX
+      )));
+    }
+    Percent::checked(value).map_err(|_| self.error(format!("{}% is out of range", value)))
+  }
+
+  /// The `mass` this field gives as percent of the `total_flour`, within the bounds of `Percent<MIN, MAX, DECIMALS>`
+  pub fn ratio_of<const MIN: usize, const MAX: usize, const DECIMALS: usize>(
+    &self,
+    mass: Gram,
+    total_flour: Gram,
+  ) -> Result<Percent<MIN, MAX, DECIMALS>> {
+    mass.ratio_of(&total_flour).map_err(|_| {
+      let percent = mass.0.checked_div(total_flour.0).unwrap_or_default().saturating_mul(PERCENT);
+      self.error(format!(
+        "{} is {:.2}% of the {} total flour, out of {}% to {}%",
+        mass, percent, total_flour, MIN, MAX
+      ))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn fields_point_at_their_position() {
+    let description = Description::new("preferment", "starter:abc:100", "<name>:<args>", "starter:10:100");
+    let (_, args) = description.whole("preferment").split_once(':', "name", "args").unwrap();
+    let fields = args.split(':', &["ratio", "hydration"], 2).unwrap();
+
+    let error = diagnostics(fields[0].percent::<1, 30, 0>().unwrap_err()).remove(0);
+    assert_eq!(error.span, 8..11);
+    assert_eq!(error.field, "ratio");
+    assert_eq!(fields[1].percent::<50, 500, 0>().unwrap(), 100.try_into().unwrap());
+  }
+
+  #[test]
+  fn out_of_range_percent_explains_the_range() {
+    let description = Description::new("hydration", "200", "<percent>", "70");
+    let error = diagnostics(description.whole("hydration").percent::<50, 120, 0>().unwrap_err()).remove(0);
+    assert_eq!(error.message, "200% is out of range, hydration is 50% to 120%");
+
+    for text in ["NaN", "inf", "-inf"] {
+      let description = Description::new("hydration", text, "<percent>", "70");
+      let error = diagnostics(description.whole("hydration").percent::<0, 120, 0>().unwrap_err()).remove(0);
+      assert_eq!(error.message, format!("'{}' is not a number", text));
+    }
+  }
+
+  #[test]
+  fn masses_may_have_a_unit() {
+    let description = Description::new("enrichment", "seeds:50g", "<name>:<mass>", "seeds:50");
+    let (_, mass) = description.whole("enrichment").split_once(':', "name", "mass").unwrap();
+    assert_eq!(mass.mass().unwrap(), 50.into());
+  }
+
+  #[test]
+  fn wrong_number_of_parts() {
+    let description = Description::new("starter", "10", "<ratio>:<hydration>", "10:100");
+    let error = diagnostics(description.whole("args").split(':', &["ratio", "hydration"], 2).unwrap_err()).remove(0);
+    assert_eq!(error.message, "expected 2 parts, got 1");
+  }
+}
@@ -6,9 +6,11 @@ use std::ops::Mul;
 use std::ops::Sub;
 
 use crate::common::percent::*;
+use crate::error::Result;
 use num_traits::Num;
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
+use serde::{Serialize, Serializer};
 
 /// Result of dividing weight by weight yields a percent ratio
 pub type Ratio = Percent<0, 100_000_000, 2>;
@@ -34,21 +36,31 @@ pub type Ratio = Percent<0, 100_000_000, 2>;
 ///
 /// let starter_weight: Gram = 10.into();
 /// assert_eq!(starter_weight.0, 10.0);
-#[derive(Debug, PartialOrd, Clone, Eq, Ord, Copy)]
+#[derive(Debug, Default, PartialOrd, Clone, Eq, Ord, Copy)]
 pub struct Gram(pub Decimal);
 
 impl Gram {
   pub const ZERO: Self = Self(Decimal::ZERO);
 
-  pub fn as_ratio_of<T>(self, other: &Self) -> T
-  where
-    T: From<Decimal>,
-  {
-    // Percent::From(Decimal) expects a percentage like 10 for 10%, 15.2 for 15.2%
+  /// The mass as a percentage of `other`, out of the percentage's bounds is an error
+  pub fn ratio_of<const MIN: usize, const MAX: usize, const DECIMALS: usize>(
+    self,
+    other: &Self,
+  ) -> Result<Percent<MIN, MAX, DECIMALS>> {
+    // Percent::try_from(Decimal) expects a percentage like 10 for 10%, 15.2 for 15.2%
     // while a division of floats yields a decimal fraction like 0.1 and 0.152
     // Hence the multiplication by 100
-    // The T::from is responsible to round up by the DECIMAL resolution.
-    T::from(self.0 / other.0 * PERCENT)
+    let ratio = self.0.checked_div(other.0).unwrap_or(Decimal::MAX);
+    Percent::try_from(ratio.saturating_mul(PERCENT))
+  }
+
+  /// The mass as a percentage of `other` to show, kept within the percentage's bounds
+  pub fn as_ratio_of<const MIN: usize, const MAX: usize, const DECIMALS: usize>(
+    self,
+    other: &Self,
+  ) -> Percent<MIN, MAX, DECIMALS> {
+    let ratio = self.0.checked_div(other.0).unwrap_or_default();
+    Percent::saturating(ratio.saturating_mul(PERCENT))
   }
 }
 
@@ -174,8 +186,7 @@ impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> Mul<Percent<MIN,
 impl Div for Gram {
   type Output = Ratio;
   fn div(self, other: Gram) -> Ratio {
-    let other: Gram = other.into();
-    (self.0 / other.0).into()
+    Percent::saturating(self.0.checked_div(other.0).unwrap_or_default())
   }
 }
 
@@ -211,6 +222,13 @@ impl fmt::Display for Gram {
   }
 }
 
+/// Serialized as a plain number of grams, rounded like its display
+impl Serialize for Gram {
+  fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(self.0.round_dp(2).to_f64().unwrap_or_default())
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
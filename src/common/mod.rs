@@ -1,6 +1,8 @@
+pub mod description;
 pub mod mass;
 pub mod percent;
 
+pub use crate::common::description::{Description, Field};
 pub use crate::common::mass::Gram;
 pub use crate::common::percent::Percent;
 
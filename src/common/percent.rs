@@ -2,6 +2,7 @@ use crate::error::{Error, Result};
 
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
+use serde::{Serialize, Serializer};
 use std::{
   fmt,
   iter::Sum,
@@ -38,6 +39,16 @@ impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> Percent<MIN, MAX
     None
   }
 
+  /// Fallible construction from a percentage like 10 for 10%, 15.2 for 15.2%
+  /// rounded to `DECIMALS` digits after the decimal point, a negative or non finite one is an error
+  pub fn checked(value: f32) -> Result<Self> {
+    if !value.is_finite() || value < 0.0 {
+      return Err(Error::InvalidPercentage(0, MIN, MAX));
+    }
+    let rounding_decimal = 0.5 / 10_f32.powf(DECIMALS as f32);
+    Percent::new(((value + rounding_decimal) * Self::DECIMALS_MULTIPLIER as f32) as usize)
+  }
+
   /// Returns the Percentage as a decimal number
   /// Example 80% => 0.8
   pub fn as_decimal(&self) -> Decimal {
@@ -46,53 +57,83 @@ impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> Percent<MIN, MAX
     Decimal::from_usize(self.0).unwrap_or(Decimal::ZERO) / normalizer
   }
 
+  /// The same percentage within other bounds, e.g. a flour ratio (0% to 100%) as a `Ratio`
+  pub fn rebound<const TO_MIN: usize, const TO_MAX: usize>(self) -> Result<Percent<TO_MIN, TO_MAX, DECIMALS>> {
+    Percent::new(self.0)
+  }
+
+  /// The percentage `value` within the bounds, a smaller one is `MIN` and a larger one `MAX`.
+  /// Meant for showing ratios worked out from masses, see `Gram::ratio_of` to check them
+  pub fn saturating(value: Decimal) -> Self {
+    let scaled = match Self::scale(value) {
+      Some(scaled) => scaled,
+      None if value.is_sign_negative() => 0,
+      None => usize::MAX,
+    };
+    Self(scaled.clamp(MIN * Self::DECIMALS_MULTIPLIER, MAX * Self::DECIMALS_MULTIPLIER))
+  }
+
+  /// A percentage in `DECIMALS` digits units, `None` when it is negative or too large
+  fn scale(value: Decimal) -> Option<usize> {
+    value.checked_mul(Decimal::from(Self::DECIMALS_MULTIPLIER))?.to_usize()
+  }
+
   //returns the valid value inclusive range
   pub fn range() -> RangeInclusive<u32> {
     MIN as u32..=MAX as u32
   }
 }
 
-impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> From<usize>
+impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> TryFrom<usize>
   for Percent<MIN, MAX, DECIMALS>
 {
-  fn from(value: usize) -> Self {
-    Percent::new(value * Self::DECIMALS_MULTIPLIER).unwrap() // TODO: Result
+  type Error = Error;
+  fn try_from(value: usize) -> Result<Self> {
+    match value.checked_mul(Self::DECIMALS_MULTIPLIER) {
+      Some(value) => Percent::new(value),
+      None => Err(Error::InvalidPercentage(value, MIN, MAX)),
+    }
   }
 }
 
-impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> From<i32>
+impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> TryFrom<i32>
   for Percent<MIN, MAX, DECIMALS>
 {
-  fn from(value: i32) -> Self {
-    Percent::new(value as usize * Self::DECIMALS_MULTIPLIER).unwrap() // TODO: Result && Maybe TryFrom
+  type Error = Error;
+  fn try_from(value: i32) -> Result<Self> {
+    let value = usize::try_from(value).map_err(|_| Error::InvalidPercentage(0, MIN, MAX))?;
+    Percent::try_from(value)
   }
 }
 
-impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> From<u32>
+impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> TryFrom<u32>
   for Percent<MIN, MAX, DECIMALS>
 {
-  fn from(value: u32) -> Self {
-    Percent::new(value as usize * Self::DECIMALS_MULTIPLIER).unwrap() // TODO: Result && Maybe TryFrom
+  type Error = Error;
+  fn try_from(value: u32) -> Result<Self> {
+    Percent::try_from(value as usize)
   }
 }
 
-impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> From<f32>
+impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> TryFrom<f32>
   for Percent<MIN, MAX, DECIMALS>
 {
-  fn from(value: f32) -> Self {
-    let rounding_decimal = 0.5 / 10_f32.powf(DECIMALS as f32);
-    Percent::new(((value + rounding_decimal) * Self::DECIMALS_MULTIPLIER as f32) as usize).unwrap()
-    // TODO: Result
+  type Error = Error;
+  fn try_from(value: f32) -> Result<Self> {
+    Percent::checked(value)
   }
 }
 
-impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> From<Decimal>
+/// A percentage like 10 for 10%, 15.2 for 15.2%, truncated to `DECIMALS` digits after the decimal point
+impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> TryFrom<Decimal>
   for Percent<MIN, MAX, DECIMALS>
 {
-  fn from(value: Decimal) -> Self {
-    Percent::new(
-      (value * Decimal::from_usize(Self::DECIMALS_MULTIPLIER).unwrap()).to_usize().unwrap()
-    ).unwrap()
+  type Error = Error;
+  fn try_from(value: Decimal) -> Result<Self> {
+    match Self::scale(value) {
+      Some(value) => Percent::new(value),
+      None => Err(Error::InvalidPercentage(usize::MAX, MIN, MAX)),
+    }
   }
 }
 
@@ -108,6 +149,15 @@ impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> fmt::Display
   }
 }
 
+/// Serialized as a plain number, i.e. 12.5 for 12.5%
+impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> Serialize
+  for Percent<MIN, MAX, DECIMALS>
+{
+  fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(self.0 as f64 / Self::DECIMALS_MULTIPLIER as f64)
+  }
+}
+
 impl<const MIN: usize, const MAX: usize, const DECIMALS: usize> Div<Percent<MIN, MAX, DECIMALS>>
   for Decimal
 {
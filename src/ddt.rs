@@ -0,0 +1,175 @@
+use crate::common::mass::*;
+use crate::common::{Description, Field};
+use crate::Result;
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use std::fmt;
+
+/// The temperatures, in °C, the desired dough temperature (DDT) is reached from.
+///
+/// Only the water's temperature is easily controlled, so it makes up for the others
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ddt {
+  /// The desired dough temperature
+  pub target: f32,
+  pub flour: f32,
+  pub room: f32,
+
+  /// The heat the mixing adds to the dough, about 1°C to 3°C by hand, more with a mixer
+  pub friction: f32,
+
+  /// The temperature of the preferments, the room's when not given
+  pub preferment: Option<f32>,
+
+  /// The coldest water the tap gives, colder water is cooled with ice
+  pub tap: f32,
+}
+
+/// The water added to the dough, at the temperature reaching the desired dough temperature
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tempering {
+  /// The temperature the water should be at, in °C
+  pub temperature: f32,
+
+  /// The water to add, at the tap's temperature when cooled with ice
+  pub water: Gram,
+
+  /// The ice to add along with the water, part of the water's mass
+  pub ice: Gram,
+
+  /// The temperature of the water added with the ice, in °C
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tap: Option<f32>,
+}
+
+impl Ddt {
+  /// The coldest water a tap gives, unless told otherwise
+  pub const DEFAULT_TAP: f32 = 10.0;
+
+  /// The heat it takes to melt a gram of ice, as °C of a gram of water
+  const ICE_MELTING: f32 = 80.0;
+
+  pub fn new(target: f32, flour: f32, room: f32, friction: f32) -> Self {
+    Ddt {
+      target,
+      flour,
+      room,
+      friction,
+      preferment: None,
+      tap: Self::DEFAULT_TAP,
+    }
+  }
+
+  pub fn preferment(mut self, preferment: f32) -> Self {
+    self.preferment = Some(preferment);
+    self
+  }
+
+  pub fn tap(mut self, tap: f32) -> Self {
+    self.tap = tap;
+    self
+  }
+
+  /// Parses a `<target>:<flour>:<room>:<friction>[:<preferment>]` description, e.g. `24:20:22:2:26`
+  pub fn parse(desc: &str) -> Result<Self> {
+    let description =
+      Description::new("ddt", desc, "<target>:<flour>:<room>:<friction>[:<preferment>]", "24:20:22:2:26");
+    let fields = description.whole("ddt").split(':', &["target", "flour", "room", "friction", "preferment"], 4)?;
+    let temperature = |field: &Field| {
+      let value = field.number::<f32>()?;
+      if (-20.0..=60.0).contains(&value) {
+        Ok(value)
+      } else {
+        Err(field.error(format!("{}°C is not a kitchen's temperature, -20°C to 60°C", value)))
+      }
+    };
+
+    let friction = fields[3].number::<f32>()?;
+    if !(0.0..=30.0).contains(&friction) {
+      return Err(fields[3].error(format!("mixing adds 0°C to 30°C, not {}°C", friction)));
+    }
+    let ddt = Ddt::new(temperature(&fields[0])?, temperature(&fields[1])?, temperature(&fields[2])?, friction);
+    Ok(match fields.get(4) {
+      Some(preferment) => ddt.preferment(temperature(preferment)?),
+      None => ddt,
+    })
+  }
+
+  /// The water temperature reaching the target, each temperature counts alike
+  ///
+  /// ```text
+  ///         <water> = <target> x <factors> - (<flour> + <room> + <friction> [+ <preferment>])
+  /// ```
+  ///
+  /// The factors are the water, flour and room, and the preferment when there is one
+  pub fn water_temperature(&self, preferment: bool) -> f32 {
+    let (factors, others) = if preferment {
+      (4.0, self.flour + self.room + self.preferment.unwrap_or(self.room))
+    } else {
+      (3.0, self.flour + self.room)
+    };
+    ((self.target * factors - others - self.friction) * 10.0).round() / 10.0
+  }
+
+  /// Tempers `water`, water colder than the tap's is the tap's water cooled by melting ice in it
+  ///
+  /// ```text
+  ///         <ice> = <water> x (<tap> - <temperature>) / (<tap> + 80)
+  /// ```
+  pub fn temper(&self, water: Gram, preferment: bool) -> Tempering {
+    let temperature = self.water_temperature(preferment);
+    if temperature >= self.tap {
+      return Tempering { temperature, water, ice: Gram::ZERO, tap: None };
+    }
+
+    let share = ((self.tap - temperature) / (self.tap + Self::ICE_MELTING)).clamp(0.0, 1.0);
+    let ice = Gram((water.0 * Decimal::from_f32(share).unwrap_or_default()).round_dp(2));
+    Tempering {
+      temperature,
+      water: water - ice,
+      ice,
+      tap: Some(self.tap),
+    }
+  }
+}
+
+impl fmt::Display for Tempering {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.tap {
+      Some(tap) => write!(f, "at {}°C, {} water at {}°C and {} ice", self.temperature, self.water, tap, self.ice),
+      None => write!(f, "at {}°C", self.temperature),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn water_makes_up_for_the_other_temperatures() {
+    let ddt = Ddt::new(24.0, 20.0, 22.0, 2.0);
+    assert_eq!(ddt.water_temperature(false), 28.0);
+    assert_eq!(ddt.water_temperature(true), 30.0);
+    assert_eq!(ddt.preferment(26.0).water_temperature(true), 26.0);
+
+    let tempering = ddt.temper(500.into(), false);
+    assert_eq!((tempering.water, tempering.ice), (500.into(), Gram::ZERO));
+  }
+
+  #[test]
+  fn cold_water_is_cooled_with_ice() {
+    // A warm kitchen and a machine mixer, (24 x 3) - (26 + 30 + 14) = 2°C
+    let tempering = Ddt::new(24.0, 26.0, 30.0, 14.0).tap(15.0).temper(475.into(), false);
+    assert_eq!(tempering.temperature, 2.0);
+    assert_eq!(tempering.ice, 65.0.into());
+    assert_eq!(tempering.water + tempering.ice, 475.into());
+  }
+
+  #[test]
+  fn descriptions_are_parsed() {
+    assert_eq!(Ddt::parse("24:20:22:2:26").unwrap(), Ddt::new(24.0, 20.0, 22.0, 2.0).preferment(26.0));
+    assert_eq!(diagnostics(Ddt::parse("24:20:220:2").unwrap_err())[0].field, "room");
+  }
+}
@@ -0,0 +1,442 @@
+use crate::builder::RecipeBuilder;
+use crate::catalog::{Entries, CATALOG};
+use crate::common::mass::*;
+use crate::common::Description;
+use crate::ddt::Ddt;
+use crate::error::collect;
+use crate::fermentation::{Fermentation, Schedule};
+use crate::ingredient::{preferment, Enrichment, Soaker, Yeast};
+use crate::levain::LevainStage;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  rc::Rc,
+};
+
+/// A recipe as written down, before any of the baker's math is applied.
+///
+/// Ingredients are kept in the very same notation the command line accepts,
+/// i.e. `white:80` for flours, `starter:10:100` for preferments and `butter%5`
+/// for enrichments, so a saved document is built through the same code path as
+/// the flags it was saved from.
+///
+/// # Example
+///
+/// ```toml
+/// title = "Country rye"
+/// mass = 1000
+/// hydration = 70
+/// salt = 2.0
+/// flours = ["white:80", "rye:20"]
+/// preferments = ["starter:10:100"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecipeDocument {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub notes: Option<String>,
+
+  /// Total flour mass in gram, ignored when the `dough` weight or an `anchor` is given
+  pub mass: i32,
+
+  /// Dough weight of a single piece in gram, the total flour mass is solved to yield it
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub dough: Option<f32>,
+
+  /// Number of pieces the dough is divided to
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub pieces: Option<u32>,
+
+  /// `<ingredient>:<mass>` the total flour mass is solved so the ingredient has exactly that mass
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub anchor: Option<String>,
+
+  /// Required recipe hydration ( Liquid / Total flour mass)
+  pub hydration: i32,
+
+  /// Salt content as percentage of flour
+  pub salt: f32,
+
+  /// `<name>:<ratio>` flour descriptions
+  pub flours: Vec<String>,
+
+  /// `<name>:<args>` preferment descriptions
+  pub preferments: Vec<String>,
+
+  /// `<name>:<mass>[:<water>]` or `<name>%<ratio>[:<water>]` enrichment descriptions
+  pub enrichments: Vec<String>,
+
+  /// `<name>:<grain>:<water>[:<gain>]` soaker descriptions
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub soakers: Vec<String>,
+
+  /// `<type>:<ratio>[@<used type>]` commercial yeast description
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub yeast: Option<String>,
+
+  /// `<seed>:<flour>:<water>[@<flour name>]` stages building the starter from a seed culture
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub levain: Vec<String>,
+
+  /// Extra levain to build, as percent of what the recipe needs
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub levain_overage: Option<f32>,
+
+  /// The dough temperature in °C, for the fermentation estimate
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub temperature: Option<f32>,
+
+  /// `<start>-<end>` of the bulk fermentation, the starter or the yeast is solved for
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub schedule: Option<String>,
+
+  /// `<target>:<flour>:<room>:<friction>[:<preferment>]` temperatures in °C, the water added is tempered by
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ddt: Option<String>,
+
+  /// The coldest water the tap gives in °C, colder water is cooled with ice
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tap_temperature: Option<f32>,
+
+  /// TOML file of the preferment types the `preferments` may use, in addition to the built in ones
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub preferment_types: Option<PathBuf>,
+
+  /// TOML file of the ingredients added to the built in catalog
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub catalog: Option<PathBuf>,
+}
+
+impl Default for RecipeDocument {
+  fn default() -> Self {
+    RecipeDocument {
+      title: None,
+      notes: None,
+      mass: 600,
+      dough: None,
+      pieces: None,
+      anchor: None,
+      hydration: 70,
+      salt: 2.0,
+      flours: vec!["White:100".to_owned()],
+      preferments: vec![],
+      enrichments: vec![],
+      soakers: vec![],
+      yeast: None,
+      levain: vec![],
+      levain_overage: None,
+      temperature: None,
+      schedule: None,
+      ddt: None,
+      tap_temperature: None,
+      preferment_types: None,
+      catalog: None,
+    }
+  }
+}
+
+impl RecipeDocument {
+  fn flour(desc: &str) -> Result<(String, Ratio)> {
+    let description = Description::new("flour", desc, "<name>:<ratio>", "rye:20");
+    let (name, ratio) = description.whole("flour").split_once(':', "name", "ratio")?;
+    Ok((name.name()?, ratio.percent::<0, 100, 2>()?.rebound()?))
+  }
+
+  /// The flours' ratios are percent of the total flour, so they must add up to 100%
+  fn flour_ratios(&self, ratios: Ratio) -> Result<()> {
+    if self.flours.is_empty() || ratios == Ratio::try_from(100)? {
+      return Ok(());
+    }
+    let description = Description::new("flours", &self.flours.join(" "), "<name>:<ratio> ...", "white:80 rye:20");
+    Err(description.whole("ratios").error_with_help(
+      format!("add up to {}", ratios),
+      "flour ratios are percent of the total flour, and must add up to 100%".to_owned(),
+    ))
+  }
+
+  fn anchor(desc: &str) -> Result<(String, Gram)> {
+    let description = Description::new("anchor", desc, "<ingredient>:<mass>", "starter:180");
+    let (name, mass) = description.whole("anchor").split_once(':', "ingredient", "mass")?;
+    Ok((name.name()?, mass.mass()?))
+  }
+
+  /// A whole number property, e.g. the mass or the hydration
+  fn property<T: std::fmt::Display>(kind: &'static str, value: T, syntax: &str, example: &str) -> Description {
+    Description::new(kind, &value.to_string(), syntax, example)
+  }
+
+  /// The builder of the recipe described by the document.
+  /// All the invalid descriptions are reported at once, each pointing at what is wrong with it
+  pub fn builder(&self) -> Result<RecipeBuilder> {
+    let mut errors = vec![];
+    let mut builder = RecipeBuilder::new(self.mass.max(0))
+      .hydration(self.hydration.max(0) as u32)
+      .salt(self.salt);
+
+    if self.dough.is_none() && self.anchor.is_none() {
+      let mass = Self::property("mass", self.mass, "<gram>", "1000");
+      collect(&mut errors, mass.whole("mass").mass());
+    }
+    let hydration = Self::property("hydration", self.hydration, "<percent>", "70");
+    collect(&mut errors, hydration.whole("hydration").percent::<50, 120, 0>());
+    let salt = Self::property("salt", self.salt, "<percent>", "2");
+    collect(&mut errors, salt.whole("salt").percent::<1, 4, 1>());
+
+    if let Some(title) = &self.title {
+      builder = builder.title(title);
+    }
+    if let Some(notes) = &self.notes {
+      builder = builder.notes(notes);
+    }
+    if let Some(pieces) = self.pieces {
+      if pieces == 0 {
+        let description = Self::property("pieces", pieces, "<count>", "4");
+        errors.push(description.whole("pieces").error("a dough is divided to at least 1 piece".to_owned()));
+      }
+      builder = builder.pieces(pieces);
+    }
+    builder = match (self.dough, &self.anchor) {
+      (Some(_), Some(_)) => return Err(Error::ConflictingScaling),
+      (Some(weight), None) => {
+        let dough = Self::property("dough", weight, "<gram>", "900");
+        match collect(&mut errors, dough.whole("dough").mass()) {
+          Some(weight) => builder.dough(weight, self.pieces.unwrap_or(1)),
+          None => builder,
+        }
+      }
+      (None, Some(anchor)) => match collect(&mut errors, Self::anchor(anchor)) {
+        Some((name, mass)) => builder.anchor(name, mass),
+        None => builder,
+      },
+      (None, None) => builder,
+    };
+
+    let mut ratios = Ratio::ZERO;
+    for flour in &self.flours {
+      if let Some((name, ratio)) = collect(&mut errors, Self::flour(flour)) {
+        ratios = ratios + ratio;
+        builder = builder.flour(name, ratio);
+      }
+    }
+    if errors.is_empty() {
+      collect(&mut errors, self.flour_ratios(ratios));
+    }
+
+    // The ingredients are validated against the total flour, or an arbitrary one when it is solved for,
+    // they are built again once it is known
+    let total_flour = match (self.dough, &self.anchor) {
+      (None, None) if self.mass > 0 => Rc::new(Gram::from(self.mass)),
+      _ => Rc::new(1000.into()),
+    };
+    for preferment in &self.preferments {
+      if collect(&mut errors, preferment::BUILDER.get(preferment, &total_flour)).is_some() {
+        builder = builder.preferment_desc(preferment);
+      }
+    }
+    for enrichment in &self.enrichments {
+      if collect(&mut errors, Enrichment::parse(enrichment, &total_flour)).is_some() {
+        builder = builder.enrichment_desc(enrichment);
+      }
+    }
+
+    for soaker in &self.soakers {
+      if collect(&mut errors, Soaker::parse(soaker, &total_flour)).is_some() {
+        builder = builder.soaker_desc(soaker);
+      }
+    }
+    if let Some(yeast) = &self.yeast {
+      if collect(&mut errors, Yeast::parse(yeast, &total_flour)).is_some() {
+        builder = builder.yeast_desc(yeast);
+      }
+    }
+
+    for stage in &self.levain {
+      if let Some(stage) = collect(&mut errors, LevainStage::parse(stage)) {
+        builder = builder.levain_stage(stage);
+      }
+    }
+    if let Some(overage) = self.levain_overage {
+      let description = Self::property("levain overage", overage, "<percent>", "10");
+      collect(&mut errors, description.whole("overage").percent::<0, 50, 0>());
+      builder = builder.levain_overage(overage);
+    }
+    if let Some(temperature) = self.temperature {
+      if !Fermentation::TEMPERATURES.contains(&temperature) {
+        let description = Self::property("temperature", temperature, "<celsius>", "24");
+        errors.push(description.whole("temperature").error("the fermentation is estimated for 15°C to 32°C".to_owned()));
+      }
+      builder = builder.temperature(temperature);
+    }
+    if let Some(schedule) = &self.schedule {
+      if let Some(schedule) = collect(&mut errors, Schedule::parse(schedule)) {
+        builder = builder.schedule(schedule);
+      }
+    }
+    if let Some(ddt) = &self.ddt {
+      if let Some(ddt) = collect(&mut errors, Ddt::parse(ddt)) {
+        builder = match self.tap_temperature {
+          Some(tap) => builder.ddt(ddt.tap(tap)),
+          None => builder.ddt(ddt),
+        };
+      }
+    }
+    if let Some(tap) = self.tap_temperature {
+      if !(0.0..=40.0).contains(&tap) {
+        let description = Self::property("tap temperature", tap, "<celsius>", "12");
+        errors.push(description.whole("tap").error("tap water is 0°C to 40°C".to_owned()));
+      }
+    }
+
+    Error::merge(errors)?;
+    Ok(builder)
+  }
+
+  pub fn from_toml(content: &str) -> Result<Self> {
+    toml::from_str(content).map_err(|e| Error::InvalidRecipeFile(e.to_string()))
+  }
+
+  pub fn to_toml(&self) -> Result<String> {
+    toml::to_string(self).map_err(|e| Error::InvalidRecipeFile(e.to_string()))
+  }
+
+  /// Registers the preferment types and the catalog entries the document refers to, before it is built
+  pub fn register(&self) -> Result<()> {
+    if let Some(path) = &self.catalog {
+      CATALOG.register(Entries::load(path)?);
+    }
+    if let Some(path) = &self.preferment_types {
+      preferment::BUILDER.register(preferment::PrefermentTypes::load(path)?)?;
+    }
+    Ok(())
+  }
+
+  pub fn load(path: &Path) -> Result<Self> {
+    let content = fs::read_to_string(path)
+      .map_err(|e| Error::RecipeFileAccess(path.display().to_string(), e.to_string()))?;
+    Self::from_toml(&content)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<()> {
+    fs::write(path, self.to_toml()?)
+      .map_err(|e| Error::RecipeFileAccess(path.display().to_string(), e.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn toml_round_trip() {
+    let document = RecipeDocument {
+      title: Some("Country rye".to_owned()),
+      notes: Some("Bake at 250c with steam".to_owned()),
+      mass: 1000,
+      dough: Some(900.0),
+      pieces: Some(3),
+      anchor: Some("starter:180".to_owned()),
+      hydration: 65,
+      salt: 2.5,
+      flours: vec!["white:80".to_owned(), "rye:20".to_owned()],
+      preferments: vec!["starter:10:100".to_owned()],
+      enrichments: vec!["butter%5".to_owned(), "seeds:50".to_owned()],
+      soakers: vec!["oats:20:300:200".to_owned()],
+      yeast: Some("instant:0.4@fresh".to_owned()),
+      levain: vec!["1:2:2".to_owned(), "1:5:5@rye".to_owned()],
+      levain_overage: Some(10.0),
+      temperature: Some(26.0),
+      schedule: Some("09:00-18:00".to_owned()),
+      ddt: Some("24:20:22:2:26".to_owned()),
+      tap_temperature: Some(12.0),
+      preferment_types: Some(PathBuf::from("/recipes/preferments.toml")),
+      catalog: Some(PathBuf::from("/recipes/catalog.toml")),
+    };
+
+    let content = document.to_toml().unwrap();
+    assert_eq!(RecipeDocument::from_toml(&content).unwrap(), document);
+  }
+
+  #[test]
+  fn missing_fields_fall_back_to_defaults() {
+    let document = RecipeDocument::from_toml("hydration = 80").unwrap();
+    assert_eq!(document.hydration, 80);
+    assert_eq!(document.mass, RecipeDocument::default().mass);
+    assert_eq!(document.flours, RecipeDocument::default().flours);
+  }
+
+  #[test]
+  fn missing_preferment_types_are_an_error() {
+    let document = RecipeDocument {
+      preferment_types: Some(PathBuf::from("/no/such/preferments.toml")),
+      ..Default::default()
+    };
+    assert!(matches!(document.register(), Err(Error::ConfigFileAccess(..))));
+  }
+
+  #[test]
+  fn invalid_descriptions_are_errors() {
+    let invalid = |document: RecipeDocument| diagnostics(document.builder().err().unwrap());
+
+    let flour = invalid(RecipeDocument {
+      flours: vec!["white".to_owned()],
+      ..Default::default()
+    });
+    assert_eq!(flour[0].message, "missing ':' separating <name> and <ratio>");
+
+    let enrichment = invalid(RecipeDocument {
+      enrichments: vec!["butter%abc".to_owned()],
+      ..Default::default()
+    });
+    assert_eq!((enrichment[0].field.as_str(), enrichment[0].span.clone()), ("ratio", 7..10));
+
+    let enrichment = invalid(RecipeDocument {
+      mass: 600,
+      enrichments: vec!["milk:99999999999".to_owned()],
+      ..Default::default()
+    });
+    assert_eq!((enrichment[0].field.as_str(), enrichment[0].span.clone()), ("mass", 5..16));
+
+    let anchor = invalid(RecipeDocument {
+      anchor: Some("starter:-1".to_owned()),
+      ..Default::default()
+    });
+    assert_eq!(anchor[0].message, "-1g is not a positive mass");
+  }
+
+  #[test]
+  fn all_invalid_descriptions_are_reported() {
+    let document = RecipeDocument {
+      hydration: 200,
+      flours: vec!["white:80".to_owned(), "rye:x".to_owned()],
+      preferments: vec!["stater:10:100".to_owned()],
+      ..Default::default()
+    };
+    let diagnostics = diagnostics(document.builder().err().unwrap());
+    let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind.as_str()).collect();
+    assert_eq!(kinds, ["hydration", "flour", "preferment"]);
+    assert!(diagnostics[2].help.contains("did you mean 'starter'?"));
+  }
+
+  #[test]
+  fn flour_ratios_add_up_to_100() {
+    let document = RecipeDocument {
+      flours: vec!["white:80".to_owned(), "rye:10".to_owned()],
+      ..Default::default()
+    };
+    assert_eq!(diagnostics(document.builder().err().unwrap())[0].message, "add up to 90.00%");
+  }
+
+  #[test]
+  fn invalid_content_is_an_error() {
+    assert!(matches!(
+      RecipeDocument::from_toml("mass = \"a lot\""),
+      Err(Error::InvalidRecipeFile(_))
+    ));
+  }
+}
@@ -1,7 +1,9 @@
 // use rust_decimal::Decimal;
+use std::{fmt, ops::Range};
 use thiserror::Error;
 
 use crate::common::{mass::Ratio, Gram};
+use crate::fermentation::Minutes;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -10,24 +12,152 @@ pub enum Error {
   #[error("Percentage value {0} must be between {1} and {2} including")]
   InvalidPercentage(usize, usize, usize),
 
-  #[error("Invalid flour:ratio format {0}")]
-  InvalidFlourArg(String),
-
-  #[error("Invalid <preferment name>:<colon separated additional args> format {0}")]
-  InvalidPrefermentArgs(String),
-
-  #[error("Preferment '{0}' not supported{0}")]
-  InvalidPreferment(String),
+  #[error("{}", Diagnostic::describe_all(.0))]
+  InvalidArguments(Vec<Diagnostic>),
 
   #[error("Not enough flour to be used by preferment, total flour {0}g requested preferment {1}g")]
   InsufficientFlour(Gram, Gram),
 
+  #[error("No flour named '{0}' in the mix to take the preferment's flour from")]
+  UnknownFlour(String),
+
+  #[error("Not enough {0} flour to be used by preferment, {1} left requested {2}")]
+  InsufficientNamedFlour(String, Gram, Gram),
+
+  #[error("The preferments hold {1} {0}, more than the recipe's {2}")]
+  ExcessCarried(String, Gram, Gram),
+
+  #[error("A recipe requires some flour")]
+  NoFlour,
+
   #[error("A total description of 100% of the four is required got {0}%")]
   InsufficientFLourRatios(Ratio),
 
-  #[error("Starter expects <ratio>:<Hydration> Arguments. Got {0}")]
-  InvalidStarterArgs(String),
+  #[error("No total flour mass yields a recipe of {0}")]
+  UnreachableTarget(Gram),
+
+  #[error("No ingredient named '{0}' in recipe")]
+  UnknownIngredient(String),
+
+  #[error("A recipe can be scaled either by dough weight or by an anchor, not both")]
+  ConflictingScaling,
+
+  #[error("A dough is divided to at least 1 piece, got {0}")]
+  InvalidPieces(u32),
+
+  #[error("Dough temperature {0}°C is out of the 15°C to 32°C fermentation is estimated for")]
+  InvalidTemperature(f32),
+
+  #[error("A {0} bulk needs {2:.2}% {1}, out of its {3}% to {4}% range")]
+  UnreachableSchedule(Minutes, String, f64, u32, u32),
+
+  #[error("Only a recipe with a starter or yeast can be fermented on schedule")]
+  NothingToSchedule,
+
+  #[error("A bulk fermentation on schedule takes some time, it can't end as it starts")]
+  EmptyBulk,
+
+  #[error("Can't access recipe file {0}: {1}")]
+  RecipeFileAccess(String, String),
+
+  #[error("Invalid recipe file: {0}")]
+  InvalidRecipeFile(String),
+
+  #[error("Can't access config file {0}: {1}")]
+  ConfigFileAccess(String, String),
+
+  #[error("Invalid config file: {0}")]
+  InvalidConfigFile(String),
+
+  #[error("Failed to serialize the recipe: {0}")]
+  Serialization(String),
+}
+
+impl Error {
+  /// Merges the diagnostics of all `errors` into a single error, reporting all the bad arguments at once.
+  /// Errors other than `InvalidArguments` are reported as is, the first one first
+  pub fn merge(errors: Vec<Error>) -> Result<()> {
+    let mut diagnostics = vec![];
+    for error in errors {
+      match error {
+        Error::InvalidArguments(more) => diagnostics.extend(more),
+        other => return Err(other),
+      }
+    }
+
+    if diagnostics.is_empty() {
+      Ok(())
+    } else {
+      Err(Error::InvalidArguments(diagnostics))
+    }
+  }
+}
+
+/// A conversion that can't fail, e.g. of a `Ratio` to a `Ratio`
+impl From<std::convert::Infallible> for Error {
+  fn from(never: std::convert::Infallible) -> Self {
+    match never {}
+  }
+}
+
+/// Points at the part of a user's argument that is wrong, why, and how to fix it
+///
+/// ```text
+/// invalid flour 'rye:abc'
+///     rye:abc
+///         ^^^ ratio: 'abc' is not a number
+///     help: flour syntax is <name>:<ratio>, e.g. rye:20
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  /// What the argument describes, e.g. `flour`
+  pub kind: String,
+
+  /// The argument as given by the user
+  pub text: String,
+
+  /// The offending part of `text`
+  pub span: Range<usize>,
+
+  /// The name of the offending part, e.g. `ratio`
+  pub field: String,
+
+  pub message: String,
+
+  pub help: String,
+}
+
+impl Diagnostic {
+  fn describe_all(diagnostics: &[Diagnostic]) -> String {
+    let count = match diagnostics.len() {
+      1 => "1 invalid argument".to_owned(),
+      n => format!("{} invalid arguments", n),
+    };
+    diagnostics.iter().fold(count, |s, d| format!("{}\n\n{}", s, d))
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let pad = " ".repeat(self.text[..self.span.start].chars().count());
+    let marker = "^".repeat(self.text[self.span.clone()].chars().count().max(1));
+    writeln!(f, "invalid {} '{}'", self.kind, self.text)?;
+    writeln!(f, "    {}", self.text)?;
+    writeln!(f, "    {}{} {}: {}", pad, marker, self.field, self.message)?;
+    write!(f, "    help: {}", self.help)
+  }
+}
+
+/// The diagnostics of an `InvalidArguments` error, any other error fails the test
+#[cfg(test)]
+pub(crate) fn diagnostics(error: Error) -> Vec<Diagnostic> {
+  match error {
+    Error::InvalidArguments(diagnostics) => diagnostics,
+    other => panic!("unexpected {:?}", other),
+  }
+}
 
-  #[error("Enrichment Arguments syntax is <name>:<mass> or <name>%<ratio>")]
-  InvalidEnrichmentArg(String),
+/// The value of `result`, or `None` once its error is kept in `errors` to be reported with all the others
+pub(crate) fn collect<T>(errors: &mut Vec<Error>, result: Result<T>) -> Option<T> {
+  result.map_err(|e| errors.push(e)).ok()
 }
@@ -0,0 +1,479 @@
+use crate::catalog::{Kind, CATALOG};
+use crate::common::mass::*;
+use crate::common::{Description, Field};
+use crate::ingredient::Ingredient;
+use crate::recipe::Recipe;
+use crate::{Error, Result};
+use prettytable::{format, row, Table};
+use rust_decimal::prelude::*;
+use serde::{Serialize, Serializer};
+use std::{fmt, ops::RangeInclusive};
+
+/// What raises the dough, as brought in by an ingredient
+#[derive(Debug, Clone, PartialEq)]
+pub enum Leavening {
+  /// A sourdough culture, by the flour it fermented
+  Sourdough(Gram),
+
+  /// A preferment started with commercial yeast, by its flour and the instant yeast it was started with
+  Preferment { flour: Gram, yeast: Gram },
+
+  /// Commercial yeast added to the final dough, as instant yeast
+  Yeast(Gram),
+}
+
+/// The culture a schedule solves for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Culture {
+  Starter,
+  Yeast,
+}
+
+/// A fermentation time, rounded to 5 minutes and serialized as minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct Minutes(pub u32);
+
+/// A bulk fermentation planned between two times of the day, e.g. `09:00-18:00`.
+/// A schedule ending before it starts ends the next day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+  /// Minutes since midnight
+  pub start: Minutes,
+  pub end: Minutes,
+}
+
+/// How long a preferment takes to peak
+#[derive(Debug, Clone, Serialize)]
+pub struct Peak {
+  pub name: String,
+
+  /// The culture the preferment is started with, e.g. `1:5:5` for a starter or `0.1% yeast` for a poolish
+  pub culture: String,
+
+  pub time: Minutes,
+}
+
+/// The estimated fermentation times of the recipe's dough
+#[derive(Debug, Clone, Serialize)]
+pub struct Fermentation {
+  /// The dough temperature, in °C
+  pub temperature: f32,
+
+  /// The flour fermented in preferments, as percent of the total flour
+  pub prefermented_flour: Ratio,
+
+  /// The commercial yeast added to the final dough as instant yeast, as percent of the total flour
+  pub yeast: Ratio,
+
+  /// Until the dough rose by about 75% (sourdough) or doubled (yeast)
+  pub bulk: Minutes,
+
+  /// From shaping to baking
+  pub proof: Minutes,
+
+  /// The schedule the recipe's culture was solved for
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub schedule: Option<Schedule>,
+
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub peaks: Vec<Peak>,
+}
+
+/// The hours a dough takes to rise at `REFERENCE_TEMPERATURE` by the culture it is started with,
+/// the more culture the faster, though less so the more there is
+///
+/// ```text
+///         <hours> = hours x <culture percent of the flour> ^ -exponent
+/// ```
+struct Curve {
+  /// The hours with 1% culture
+  hours: f64,
+  exponent: f64,
+}
+
+impl Curve {
+  fn hours(&self, percent: f64) -> f64 {
+    self.hours * percent.powf(-self.exponent)
+  }
+
+  /// The culture, as percent of the flour, rising in `hours`
+  fn percent(&self, hours: f64) -> f64 {
+    (self.hours / hours).powf(1.0 / self.exponent)
+  }
+}
+
+/// 20% prefermented flour rises in 5 hours, 10% in 6.5 hours and 2% in 12 hours
+const SOURDOUGH: Curve = Curve { hours: 15.6, exponent: 0.38 };
+
+/// A ripe yeasted preferment is more vigorous than a sourdough culture
+const PREFERMENT: Curve = Curve { hours: 11.7, exponent: 0.38 };
+
+/// 1% instant yeast rises in 2 hours, 0.1% in 8 hours
+const YEAST: Curve = Curve { hours: 2.0, exponent: 0.6 };
+
+impl Minutes {
+  fn from_hours(hours: f64) -> Self {
+    Minutes(((hours * 12.0).round() * 5.0) as u32)
+  }
+
+  fn hours(&self) -> f64 {
+    self.0 as f64 / 60.0
+  }
+}
+
+impl fmt::Display for Minutes {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match (self.0 / 60, self.0 % 60) {
+      (0, minutes) => write!(f, "{}m", minutes),
+      (hours, 0) => write!(f, "{}h", hours),
+      (hours, minutes) => write!(f, "{}h {:02}m", hours, minutes),
+    }
+  }
+}
+
+impl Leavening {
+  /// The hours it alone would take to raise a dough of `total_flour` at `Fermentation::REFERENCE_TEMPERATURE`
+  fn hours(&self, total_flour: Gram) -> f64 {
+    let percent = |mass: &Gram| (mass.0 * Decimal::ONE_HUNDRED / total_flour.0).to_f64().unwrap_or_default();
+    match self {
+      Leavening::Sourdough(flour) => SOURDOUGH.hours(percent(flour)),
+      Leavening::Preferment { flour, .. } => PREFERMENT.hours(percent(flour)),
+      Leavening::Yeast(yeast) => YEAST.hours(percent(yeast)),
+    }
+  }
+
+  fn is(&self, culture: Culture) -> bool {
+    matches!((self, culture), (Leavening::Sourdough(_), Culture::Starter) | (Leavening::Yeast(_), Culture::Yeast))
+  }
+}
+
+impl Schedule {
+  /// Parses a `<start>-<end>` description, e.g. `09:00-18:00`
+  pub fn parse(desc: &str) -> Result<Self> {
+    let description = Description::new("schedule", desc, "<start>-<end>", "09:00-18:00");
+    let (start, end) = description.whole("schedule").split_once('-', "start", "end")?;
+    let schedule = Schedule {
+      start: Self::time(&start)?,
+      end: Self::time(&end)?,
+    };
+    if schedule.bulk() == Minutes(0) {
+      return Err(end.error(format!("the bulk ends at {} as it starts, it takes no time", end.text())));
+    }
+    Ok(schedule)
+  }
+
+  fn time(field: &Field) -> Result<Minutes> {
+    let (hours, minutes) = field.split_once(':', "hours", "minutes")?;
+    let (h, m) = (hours.number::<u32>()?, minutes.number::<u32>()?);
+    if h >= 24 || m >= 60 {
+      return Err(field.error(format!("{} is not a time of the day, 00:00 to 23:59", field.text())));
+    }
+    Ok(Minutes(h * 60 + m))
+  }
+
+  /// The time from start to end
+  pub fn bulk(&self) -> Minutes {
+    Minutes((self.end.0 + 24 * 60 - self.start.0) % (24 * 60))
+  }
+}
+
+impl fmt::Display for Schedule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let time = |m: Minutes| format!("{:02}:{:02}", m.0 / 60, m.0 % 60);
+    write!(f, "{}-{}", time(self.start), time(self.end))
+  }
+}
+
+impl Serialize for Schedule {
+  fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl Fermentation {
+  /// The dough temperature the curves are measured at, in °C
+  pub const REFERENCE_TEMPERATURE: f32 = 24.0;
+
+  /// The dough temperatures the estimate holds for, in °C. Colder doughs (i.e. retarded in the fridge) slow
+  /// down more than the model predicts, warmer doughs go slack
+  pub const TEMPERATURES: RangeInclusive<f32> = 15.0..=32.0;
+
+  /// The fermentation slows down by half for every this many °C colder
+  const HALVING_DEGREES: f64 = 8.0;
+
+  /// The final proof takes this part of the bulk, the dough is already active
+  const PROOF: f64 = 0.5;
+
+  /// The fibre of white flour, bran rich flours (i.e. wholewheat, rye) ferment faster by `BRAN` for every percent
+  /// of fibre more than it
+  const WHITE_FIBRE: f64 = 3.0;
+  const BRAN: f64 = 0.02;
+
+  /// The feed a starter is taken to be built with, `seed:flour:water`, when no levain stages are given
+  pub const DEFAULT_FEED: (f64, f64, f64) = (1.0, 5.0, 5.0);
+
+  /// Estimates how long the `recipe`'s dough ferments at `temperature`, None when nothing leavens it.
+  ///
+  /// Each culture alone would raise the dough in the time its `Curve` gives, together they raise it at the sum
+  /// of their rates. The times are then scaled to the dough temperature and to the flour mix.
+  ///
+  /// These are rough estimates, cultures and kitchens differ, so watch the dough rather than the clock
+  pub fn estimate(recipe: &Recipe, temperature: f32) -> Result<Option<Self>> {
+    let total_flour = recipe.total_flour();
+    let leavenings = Self::leavenings(recipe);
+    if leavenings.is_empty() {
+      return Ok(None);
+    }
+    let slowdown = Self::slowdown(temperature)?;
+
+    let (mut rate, mut prefermented, mut instant, mut peaks) = (0.0, Gram::ZERO, Gram::ZERO, vec![]);
+    for (ingredient, leavening) in leavenings {
+      let peak = match &leavening {
+        Leavening::Sourdough(flour) => {
+          prefermented += *flour;
+          let (seed, fed, culture) = Self::feed(recipe);
+          Some((SOURDOUGH.hours(seed * 100.0 / (seed + fed)), culture))
+        }
+        Leavening::Preferment { flour, yeast } => {
+          prefermented += *flour;
+          let started: Ratio = yeast.as_ratio_of(flour);
+          let percent = (*yeast > Gram::ZERO && *flour > Gram::ZERO).then(|| yeast.0 * Decimal::ONE_HUNDRED / flour.0);
+          percent.map(|percent| (YEAST.hours(percent.to_f64().unwrap_or_default()), format!("{} yeast", started)))
+        }
+        Leavening::Yeast(yeast) => {
+          instant += *yeast;
+          None
+        }
+      };
+      let hours = leavening.hours(total_flour);
+      if hours.is_finite() {
+        rate += 1.0 / hours;
+      }
+      if let Some((hours, culture)) = peak {
+        let name = ingredient.summary(total_flour, recipe.total()).name;
+        peaks.push(Peak { name, culture, time: Minutes::from_hours(hours * slowdown) });
+      }
+    }
+    if rate == 0.0 {
+      return Ok(None);
+    }
+
+    let bulk = slowdown / rate / Self::activity(recipe);
+    Ok(Some(Fermentation {
+      temperature,
+      prefermented_flour: prefermented.as_ratio_of(&total_flour),
+      yeast: instant.as_ratio_of(&total_flour),
+      bulk: Minutes::from_hours(bulk),
+      proof: Minutes::from_hours(bulk * Self::PROOF),
+      schedule: None,
+      peaks,
+    }))
+  }
+
+  /// The percent of the total flour of the `recipe`'s `culture` raising the dough in `bulk` at `temperature`,
+  /// the other cultures as they are. The first culture of its kind is solved for, 0 when the others are faster.
+  ///
+  /// ```text
+  ///         <culture rate> = slowdown / (activity x bulk) - <other cultures rates>
+  /// ```
+  pub fn inoculation(recipe: &Recipe, temperature: f32, bulk: Minutes, culture: Culture) -> Result<f64> {
+    if bulk == Minutes(0) {
+      return Err(Error::EmptyBulk);
+    }
+    let slowdown = Self::slowdown(temperature)?;
+    let total_flour = recipe.total_flour();
+    let mut rate = slowdown / (Self::activity(recipe) * bulk.hours());
+    let mut solved = false;
+    for (_, leavening) in Self::leavenings(recipe) {
+      let hours = leavening.hours(total_flour);
+      if !solved && leavening.is(culture) {
+        solved = true;
+      } else if hours.is_finite() {
+        rate -= 1.0 / hours;
+      }
+    }
+    let curve = match culture {
+      Culture::Starter => SOURDOUGH,
+      Culture::Yeast => YEAST,
+    };
+    Ok(if rate > 0.0 { curve.percent(1.0 / rate) } else { 0.0 })
+  }
+
+  /// How many times slower the dough ferments at `temperature` than at `REFERENCE_TEMPERATURE`
+  fn slowdown(temperature: f32) -> Result<f64> {
+    if !Self::TEMPERATURES.contains(&temperature) {
+      return Err(Error::InvalidTemperature(temperature));
+    }
+    Ok(2f64.powf((Self::REFERENCE_TEMPERATURE - temperature) as f64 / Self::HALVING_DEGREES))
+  }
+
+  fn leavenings(recipe: &Recipe) -> Vec<(&dyn Ingredient, Leavening)> {
+    recipe
+      .ingredients()
+      .iter()
+      .filter_map(|i| i.leavening().map(|leavening| (i.as_ref(), leavening)))
+      .collect()
+  }
+
+  /// The seed flour and the flour fed to the starter's last build, and the feed as `seed:flour:water`.
+  /// The seed is taken to be as hydrated as what it is fed
+  fn feed(recipe: &Recipe) -> (f64, f64, String) {
+    let planned = recipe.levain.as_ref().and_then(|plan| plan.stages.last()).map(|stage| {
+      let f64 = |gram: Gram| gram.0.to_f64().unwrap_or_default();
+      let hydration = if stage.flour > Gram::ZERO { f64(stage.water) / f64(stage.flour) } else { 1.0 };
+      (f64(stage.seed) / (1.0 + hydration), f64(stage.flour), stage.ratio.clone())
+    });
+    planned.unwrap_or_else(|| {
+      let (seed, flour, water) = Self::DEFAULT_FEED;
+      (seed / (1.0 + water / flour), flour, format!("{}:{}:{}*", seed, flour, water))
+    })
+  }
+
+  /// How much faster the flour mix ferments than white flour, by the fibre of the flours known to the catalog
+  fn activity(recipe: &Recipe) -> f64 {
+    let total_flour = recipe.total_flour();
+    let (mut fibre, mut known) = (0.0, 0.0);
+    for summary in recipe.ingredients().iter().map(|i| i.summary(total_flour, recipe.total())) {
+      for component in std::iter::once(&summary).chain(summary.components.iter()) {
+        let entry = CATALOG.get(&component.name).filter(|entry| entry.kind == Kind::Flour);
+        if let (Some(entry), Some(mass)) = (entry, recipe.mass_of(&component.name)) {
+          let mass = mass.0.to_f64().unwrap_or_default();
+          fibre += entry.fibre as f64 * mass;
+          known += mass;
+        }
+      }
+    }
+    let fibre = if known > 0.0 { fibre / known } else { Self::WHITE_FIBRE };
+    1.0 + Self::BRAN * (fibre - Self::WHITE_FIBRE)
+  }
+
+  pub fn display(&self) {
+    println!("FERMENTATION AT {}°C", self.temperature);
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    if self.prefermented_flour > Ratio::ZERO {
+      table.add_row(row!["", "PREFERMENTED FLOUR", r -> self.prefermented_flour]);
+    }
+    if self.yeast > Ratio::ZERO {
+      table.add_row(row!["", "INSTANT YEAST", r -> self.yeast]);
+    }
+    match &self.schedule {
+      Some(schedule) => table.add_row(row!["", "BULK", r -> self.bulk, schedule]),
+      None => table.add_row(row!["", "BULK", r -> self.bulk]),
+    };
+    table.add_row(row!["", "FINAL PROOF", r -> self.proof]);
+    for peak in &self.peaks {
+      table.add_row(row!["", format!("{} PEAK", peak.name.to_uppercase()), r -> peak.time, peak.culture]);
+    }
+    table.printstd();
+    if self.peaks.iter().any(|peak| peak.culture.ends_with('*')) {
+      let (seed, flour, water) = Self::DEFAULT_FEED;
+      println!("* The starter is taken to be fed {}:{}:{}, give its levain stages for its own feed", seed, flour, water);
+    }
+    println!("Rough estimates at a steady dough temperature, preferments peak at it too. Watch the dough, not the clock");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+  use crate::levain::LevainStage;
+  use crate::RecipeBuilder;
+
+  fn bulk(builder: RecipeBuilder) -> Minutes {
+    builder.build().unwrap().fermentation.unwrap().bulk
+  }
+
+  #[test]
+  fn more_culture_and_warmer_dough_ferment_faster() {
+    let starter = |portion: &str, temperature: f32| {
+      bulk(RecipeBuilder::new(1000).preferment_desc(format!("starter:{}:100", portion)).temperature(temperature))
+    };
+    assert_eq!(starter("20", 24.0), Minutes(300));
+    assert!(starter("10", 24.0) > starter("20", 24.0));
+    assert_eq!(starter("20", 16.0), Minutes(600));
+
+    let wholewheat = RecipeBuilder::new(1000).flour("wholewheat", 100).preferment_desc("starter:20:100");
+    assert!(bulk(wholewheat) < starter("20", 24.0));
+  }
+
+  #[test]
+  fn cultures_add_up() {
+    let yeast = bulk(RecipeBuilder::new(1000).yeast_desc("instant:1"));
+    assert_eq!(yeast, Minutes(120));
+
+    let hybrid = bulk(RecipeBuilder::new(1000).preferment_desc("starter:20:100").yeast_desc("instant:1"));
+    assert!(hybrid < yeast);
+    assert_eq!(bulk(RecipeBuilder::new(1000).yeast_desc("fresh:3")), yeast);
+
+    assert!(RecipeBuilder::new(1000).build().unwrap().fermentation.is_none());
+  }
+
+  #[test]
+  fn preferments_peak_by_their_own_culture() {
+    let recipe = RecipeBuilder::new(1000)
+      .preferment_desc("starter:20:100")
+      .levain_stage(LevainStage::new(1.0, 1.0, 1.0))
+      .build()
+      .unwrap();
+    let peak = &recipe.fermentation.unwrap().peaks[0];
+    assert_eq!((peak.name.as_str(), peak.culture.as_str()), ("starter", "1:1:1"));
+    assert_eq!(peak.time, Minutes(245));
+
+    let recipe = RecipeBuilder::new(1000).preferment_desc("poolish:30:100:0.1").build().unwrap();
+    assert_eq!(recipe.fermentation.unwrap().peaks[0].time, Minutes(480));
+  }
+
+  #[test]
+  fn starter_is_solved_for_the_schedule() {
+    let schedule = Schedule::parse("09:00-14:00").unwrap();
+    let recipe = RecipeBuilder::new(1000)
+      .preferment_desc("starter:5:80:30")
+      .schedule(schedule)
+      .build()
+      .unwrap();
+    // 20% of the flour at 80% hydration, solved to 2 decimals
+    let starter = recipe.mass_of("starter").unwrap();
+    assert!((starter - Gram::from(360)).0.abs() < Decimal::ONE);
+    // Only its portion is solved, the culture kept from it stays
+    assert!(recipe.ingredients().iter().any(|i| i.kept() == 30.into()));
+    assert_eq!(recipe.ingredients()[0].kept(), 30.into());
+    assert_eq!(recipe.fermentation.unwrap().bulk, Minutes(300));
+
+    let overnight = Schedule::parse("22:00-08:30").unwrap();
+    assert_eq!(overnight.bulk(), Minutes(630));
+    let recipe = RecipeBuilder::new(1000).yeast_desc("fresh:1").schedule(overnight).temperature(20.0).build().unwrap();
+    let fermentation = recipe.fermentation.unwrap();
+    assert!(fermentation.bulk.0.abs_diff(630) <= 10);
+    assert_eq!(fermentation.schedule, Some(overnight));
+  }
+
+  #[test]
+  fn unreachable_schedules_are_errors() {
+    let quick = RecipeBuilder::new(1000).preferment_desc("starter:10:100").schedule(Schedule::parse("09:00-10:00").unwrap());
+    assert!(matches!(quick.build().unwrap_err(), Error::UnreachableSchedule(Minutes(60), _, _, 1, 30)));
+
+    let plain = RecipeBuilder::new(1000).schedule(Schedule::parse("09:00-18:00").unwrap());
+    assert_eq!(plain.build().unwrap_err(), Error::NothingToSchedule);
+
+    assert_eq!(diagnostics(Schedule::parse("9:00-25:00").unwrap_err())[0].field, "end");
+
+    let error = diagnostics(Schedule::parse("09:00-09:00").unwrap_err()).remove(0);
+    assert_eq!((error.field.as_str(), error.span), ("end", 6..11));
+    let empty = Schedule { start: Minutes(540), end: Minutes(540) };
+    let empty = RecipeBuilder::new(1000).preferment_desc("starter:10:100").schedule(empty);
+    assert_eq!(empty.build().unwrap_err(), Error::EmptyBulk);
+  }
+
+  #[test]
+  fn temperatures_out_of_range_are_errors() {
+    let builder = RecipeBuilder::new(1000).preferment_desc("starter:20:100").temperature(4.0);
+    assert_eq!(builder.build().unwrap_err(), Error::InvalidTemperature(4.0));
+
+    // Nothing to estimate without a culture
+    let recipe = RecipeBuilder::new(1000).enrichment("butter", 5).temperature(4.0).build().unwrap();
+    assert!(recipe.fermentation.is_none());
+  }
+}
@@ -1,36 +1,119 @@
-use super::ingredient::Ingredient;
+use super::ingredient::{Ingredient, Summary};
+use crate::catalog::CATALOG;
 use crate::common::mass::*;
+use crate::common::Description;
+use crate::{Error, Result};
 use prettytable::{row, Table};
-use rust_decimal::Decimal;
 use std::{ops::Mul, rc::Rc};
 
+/// The water content of the enrichment called `name`, if the catalog knows it to count toward the hydration
+pub fn water_content(name: &str) -> Option<Ratio> {
+  CATALOG.get(name).and_then(|entry| entry.water_content())
+}
+
 #[derive(Debug, Clone)]
 pub struct Enrichment<P> {
   pub name: String,
   pub total_mass: Rc<Gram>,
   pub ratio: P,
+
+  /// The part already in preferments (e.g. the salt in old dough), not to be added again
+  pub carried: Gram,
+
+  /// The water in it as percent of its mass (e.g. 87% of milk), counted toward the hydration
+  pub water: Ratio,
 }
 
-impl<P> Enrichment<P> 
-where 
-  P: From<usize> + std::fmt::Debug
+impl<P> Enrichment<P>
+where
+  P: std::fmt::Debug
 {
   pub fn new(name: String, total_mass: &Rc<Gram>, ratio: P) -> Self {
     Enrichment {
+      water: water_content(&name).unwrap_or(Ratio::ZERO),
       name,
       total_mass: total_mass.clone(),
       ratio,
+      carried: Gram::ZERO,
     }
   }
 
-  pub fn new_by_mass(name: String, total_mass: &Rc<Gram>, mass: Gram) -> Self 
-  where 
-   P: From<Decimal> + std::fmt::Debug + std::fmt::Display + Clone,
-   Gram: Mul<P, Output=Gram>,
-   {
-    // TODO: Formalize the mass & percent algebra
-    let ratio: P = (mass.0 / total_mass.0 * Decimal::from(100)).into();
-    Self::new(name, total_mass, ratio)
+  /// Overrides the built in water content, as percent of the enrichment's mass
+  pub fn water_content(mut self, water: Ratio) -> Self {
+    self.water = water;
+    self
+  }
+
+  pub fn carried(mut self, carried: Gram) -> Self {
+    self.carried = carried;
+    self
+  }
+}
+
+impl Enrichment<Ratio> {
+  /// An enrichment of `mass`, as percent of the total flour mass, regardless of the total flour mass
+  pub fn new_by_mass(name: String, total_mass: &Rc<Gram>, mass: Gram) -> Result<Self> {
+    Ok(Self::new(name, total_mass, mass.ratio_of(total_mass)?))
+  }
+
+  /// The enrichment described by `desc`, `<name>%<ratio>[:<water>]` e.g. `butter%5` or
+  /// `<name>:<mass>[:<water>]` e.g. `milk:200:87`, the water as percent of its mass
+  pub fn parse(desc: &str, total_mass: &Rc<Gram>) -> Result<Self> {
+    let description =
+      Description::new("enrichment", desc, "<name>%<ratio>[:<water>] or <name>:<mass>[:<water>]", "milk:200:87");
+    let whole = description.whole("enrichment");
+    let (enrichment, water) = match whole.split_once('%', "name", "ratio") {
+      Ok((name, ratio)) => {
+        let (ratio, water) = ratio.split_first(':', "ratio", "water");
+        (Self::new(name.name()?, total_mass, ratio.percent()?), water)
+      }
+      Err(_) => {
+        let (name, mass) = whole.split_once(':', "name", "mass")?;
+        let (mass, water) = mass.split_first(':', "mass", "water");
+        let name = name.name()?;
+        (Self::new(name, total_mass, mass.ratio_of(mass.mass()?, **total_mass)?), water)
+      }
+    };
+    match water.text() {
+      "" => Ok(enrichment),
+      _ => Ok(enrichment.water_content(water.percent::<0, 100, 2>()?.rebound()?)),
+    }
+  }
+}
+
+impl<P> Enrichment<P>
+where
+  P: std::fmt::Debug + Copy,
+  Gram: Mul<P, Output = Gram>,
+{
+  /// The recipe's own amount, including the part in preferments
+  fn mass(&self) -> Gram {
+    (*self.total_mass * self.ratio).0.round().into()
+  }
+
+  /// The mass to be actively added, the part in preferments is already in the dough
+  fn added(&self) -> Gram {
+    (self.mass() - self.carried).max(Gram::ZERO)
+  }
+
+  /// Takes off the part already in preferments, more than the recipe's own amount is an error
+  /// as the dough would hold more than the recipe calls for
+  pub fn carrying(self, carried: Gram) -> Result<Self> {
+    if carried > self.mass() {
+      return Err(Error::ExcessCarried(self.name.to_lowercase(), carried, self.mass()));
+    }
+    Ok(self.carried(carried))
+  }
+
+  fn comment(&self) -> String {
+    let mut comment = vec![];
+    if self.water > Ratio::ZERO {
+      comment.push(format!("{} water", Ingredient::water(self)));
+    }
+    if self.carried > Gram::ZERO {
+      comment.push(format!("{} in preferments", self.carried));
+    }
+    comment.join(", ")
   }
 }
 
@@ -39,16 +122,71 @@ where
   P: std::fmt::Debug + Copy,
   Gram: Mul<P, Output = Gram>,
 {
+  /// The water in the part to be actively added
+  fn water(&self) -> Gram {
+    <Gram as Mul<Ratio>>::mul(self.added(), self.water)
+  }
+
+  /// The solids in the part to be actively added
   fn other(&self) -> Gram {
-    (*self.total_mass * self.ratio).0.round().into()
+    self.added() - Ingredient::water(self)
   }
 
   fn describe(&self, mut table: Table, total: Gram) -> Table {
-    let other_ratio_flour: Ratio = self.other().as_ratio_of(&self.total_mass);
-    let other_ratio_total: Ratio = self.other().as_ratio_of(&total);
+    let total_ratio_flour: Ratio = self.total().as_ratio_of(&self.total_mass);
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
     table.add_row(
-      row![ b -> self.name.to_uppercase(), "", br -> self.other(), br -> other_ratio_flour, rb -> other_ratio_total],
+      row![ b -> self.name.to_uppercase(), "", br -> self.total(), br -> total_ratio_flour, rb -> total_ratio_total, self.comment()],
     );
     table
   }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of(&self.name, self, total_flour, total).with_comment(self.comment())
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    self.name.eq_ignore_ascii_case(name).then(|| self.total())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn water_counts_toward_hydration() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let milk = Enrichment::<Ratio>::new_by_mass("Milk".to_owned(), &total_flour, 200.into()).unwrap();
+    assert_eq!(milk.water(), 174.into());
+    assert_eq!(milk.other(), 26.into());
+
+    let butter = Enrichment::<Ratio>::new("butter".to_owned(), &total_flour, 10.try_into().unwrap())
+      .water_content(20.try_into().unwrap());
+    assert_eq!(butter.water(), 20.into());
+    assert_eq!(butter.total(), 100.into());
+
+    let seeds = Enrichment::<Ratio>::new("seeds".to_owned(), &total_flour, 5.try_into().unwrap());
+    assert_eq!((seeds.water(), seeds.other()), (Gram::ZERO, 50.into()));
+  }
+
+  #[test]
+  fn carried_part_is_not_added_again() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let milk = Enrichment::<Ratio>::new_by_mass("milk".to_owned(), &total_flour, 200.into()).unwrap().carried(100.into());
+    assert_eq!(milk.total(), 100.into());
+    assert_eq!(milk.water(), 87.into());
+  }
+
+  #[test]
+  fn out_of_range_mass_is_located() {
+    let total_flour = Rc::new(Gram::from(600));
+    let error = diagnostics(Enrichment::parse("milk:99999999999", &total_flour).unwrap_err());
+    assert_eq!((error[0].field.as_str(), error[0].span.clone()), ("mass", 5..16));
+    assert_eq!(
+      error[0].message,
+      "99999999999.00 g is 16666666666.50% of the 600.00 g total flour, out of 0% to 100000000%"
+    );
+  }
 }
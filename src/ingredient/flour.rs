@@ -1,7 +1,7 @@
 use prettytable::{row, Table};
 use std::rc::Rc;
 
-use super::Ingredient;
+use super::{Ingredient, Summary};
 use crate::common::mass::*;
 use crate::error::{Error, Result};
 
@@ -16,6 +16,12 @@ pub struct Flour {
   /// Flour, in grams, that shouldn't be added, because it is in preferments
   repurposed: Gram,
 
+  /// Flour, in grams, displaced by the own flour of preferments, the mix's ratios cover only the rest
+  displaced: Gram,
+
+  /// Flour, in grams, brought by preferments made of their own flour
+  contributed: Gram,
+
   /// A Reference to total flour mass in recipe
   total_mass: Rc<Gram>,
 }
@@ -26,37 +32,85 @@ impl Flour {
       name,
       ratio,
       repurposed: Gram::ZERO,
+      displaced: Gram::ZERO,
+      contributed: Gram::ZERO,
       total_mass: total_mass.clone(),
     }
   }
 
   /// The content of the flour in recipe, including the part in preferments
   pub fn gross_flour(&self) -> Gram {
-    *self.total_mass * self.ratio
+    *self.total_mass * self.ratio - self.displaced + self.contributed
+  }
+
+  fn comment(&self) -> String {
+    let content: Ratio = self.gross_flour().as_ratio_of(&self.total_mass);
+    let used_ratio: Ratio = self.repurposed.as_ratio_of(&self.gross_flour());
+    let used_comment = if self.repurposed > Gram::ZERO {
+      format!("{}({}) used in preferments", used_ratio, self.repurposed)
+    } else {
+      String::new()
+    };
+    let contributed_comment = if self.contributed > Gram::ZERO {
+      format!("{} from preferments' own flour", self.contributed)
+    } else {
+      String::new()
+    };
+    format!("{} of flour content. {}{}", content, used_comment, contributed_comment)
   }
 }
 
 impl Ingredient for Flour {
   /// Only the part to be actively added is
   fn flour(&self) -> Gram {
-    *self.total_mass * self.ratio - self.repurposed
+    *self.total_mass * self.ratio - self.displaced - self.repurposed
   }
 
   fn describe(&self, mut table: Table, total: Gram) -> Table {
     let added_ratio_flour: Ratio = self.flour().as_ratio_of(&self.total_mass);
     let added_ratio_total: Ratio = self.flour().as_ratio_of(&total);
 
-    let used = self.gross_flour() - self.flour();
-    let used_ratio: Ratio = used.as_ratio_of(&&self.gross_flour());
-    let used_comment = if used > Gram::ZERO {
-      format!("{}({}) used in preferments", used_ratio, used)
-    } else {
-      format!("")
-    };
-    let comment = format!("{} of flour content. {}", self.ratio, used_comment);
-    table.add_row(row!["", self.name, r -> self.flour(), r -> added_ratio_flour, r -> added_ratio_total, comment ]);
+    table.add_row(row!["", self.name, r -> self.flour(), r -> added_ratio_flour, r -> added_ratio_total, self.comment() ]);
     table
   }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of(&self.name, self, total_flour, total).with_comment(self.comment())
+  }
+
+  /// A flour's mass includes the part used in preferments
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    self.name.eq_ignore_ascii_case(name).then(|| self.gross_flour())
+  }
+}
+
+/// How a preferment's flour is taken from the flours of the mix
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Allocation {
+  /// From the first flours of the mix, in the order they were added
+  #[default]
+  InOrder,
+
+  /// From all the flours, in proportion to what is left of them
+  Proportional,
+
+  /// From the named flour only, e.g. a rye starter
+  Named(String),
+
+  /// The preferment brings its own flours, `<name>, <ratio>` of its flour, merged into the formula.
+  /// The mix's ratios then cover only the rest of the total flour
+  Own(Vec<(String, Ratio)>),
+}
+
+impl Allocation {
+  /// The allocation by its name in a description, `all` is proportional, anything else is a flour name
+  pub fn named(name: &str) -> Self {
+    if name.eq_ignore_ascii_case("all") {
+      Allocation::Proportional
+    } else {
+      Allocation::Named(name.to_owned())
+    }
+  }
 }
 
 /// A flour aggregator, to enforce certain constraints
@@ -72,17 +126,23 @@ impl Ingredient for Flours {
   }
 
   fn describe(&self, mut table: Table, total: Gram) -> Table {
-    let total_gross_flour = self.mix.iter().fold(Gram::ZERO, |a, f| a + f.gross_flour());
-    let total_added_flour = self.mix.iter().fold(Gram::ZERO, |a, f| a + f.flour());
+    let total_added_flour = self.flour();
     let added_ratio_flour: Ratio = total_added_flour.as_ratio_of(&self.total_mass);
     let added_ratio_total: Ratio = total_added_flour.as_ratio_of(&total);
 
-    let used = total_gross_flour - total_added_flour;
-    let used_ratio: Ratio = used.as_ratio_of(&self.total_mass);
-    let comment = format!("{}({}) used in preferments", used_ratio, used);
-    table.add_row(row![ b -> "FLOUR", "", rb -> total_added_flour, rb -> added_ratio_flour, rb -> added_ratio_total, b -> comment]);
+    table.add_row(row![ b -> "FLOUR", "", rb -> total_added_flour, rb -> added_ratio_flour, rb -> added_ratio_total, b -> self.comment()]);
     self.mix.iter().fold(table, |t, f| f.describe(t, total))
   }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("flour", self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(self.mix.iter().map(|f| f.summary(total_flour, total)).collect())
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    self.mix.iter().find_map(|f| f.mass_of(name))
+  }
 }
 
 impl Flours {
@@ -93,6 +153,13 @@ impl Flours {
     }
   }
 
+  fn comment(&self) -> String {
+    let total_gross_flour = self.mix.iter().fold(Gram::ZERO, |a, f| a + f.gross_flour());
+    let used = total_gross_flour - self.flour();
+    let used_ratio: Ratio = used.as_ratio_of(&self.total_mass);
+    format!("{}({}) used in preferments", used_ratio, used)
+  }
+
   /// Add a flour to the flour Mix
   /// Flours can be added by ratio (TODO: Maybe by mass, if intresting)
   pub fn add_flour(mut self, name: String, ratio: Ratio) -> Self {
@@ -104,49 +171,97 @@ impl Flours {
 
   /// Get the sum of all flour ratios
   pub fn total_ratio(&self) -> Result<Ratio> {
-    let total_ratio: Ratio = self.mix.iter().fold(0.into(), |a, f| a + f.ratio);
-    if total_ratio != 100.into() {
+    let total_ratio: Ratio = self.mix.iter().fold(Ratio::ZERO, |a, f| a + f.ratio);
+    if total_ratio != Ratio::try_from(100)? {
       Err(Error::InsufficientFLourRatios(total_ratio))
     } else {
-      Ok(total_ratio.into())
+      Ok(total_ratio)
     }
   }
 
-  /// Removes the part of flour used by preferments from the added flour
+  /// Removes the part of flour used by preferments from the added flour, taking it from the first flours in order
   /// Precondition: Should only be called on Flours mix with 100% ratio sum.
   pub fn repurpose(self, ingredient: &dyn Ingredient) -> Result<Self> {
-    let mut q = ingredient.flour();
-    let mix = self
-      .mix
-      .into_iter()
-      .map(|f| {
-        let used = std::cmp::min(f.flour(), q);
-        q = q - used;
-
-        Flour {
-          name: f.name,
-          ratio: f.ratio,
-          total_mass: f.total_mass,
-          repurposed: f.repurposed + used,
+    self.repurpose_with(ingredient, &Allocation::InOrder)
+  }
+
+  /// Removes the part of flour used by preferments from the added flour, as directed by `allocation`
+  pub fn repurpose_with(mut self, ingredient: &dyn Ingredient, allocation: &Allocation) -> Result<Self> {
+    let requested = ingredient.flour();
+    let mix_flour: Vec<Gram> = self.mix.iter().map(|f| f.flour()).collect();
+    let available = mix_flour.iter().fold(Gram::ZERO, |a, f| a + *f);
+    if requested > available {
+      return Err(Error::InsufficientFlour(*self.total_mass, requested));
+    }
+
+    match allocation {
+      Allocation::InOrder => {
+        let mut q = requested;
+        for f in self.mix.iter_mut() {
+          let used = std::cmp::min(f.flour(), q);
+          q = q - used;
+          f.repurposed += used;
         }
-      })
-      .collect();
+      }
+      Allocation::Proportional => {
+        for (f, used) in self.mix.iter_mut().zip(Self::shares(&mix_flour, requested, available)) {
+          f.repurposed += used;
+        }
+      }
+      Allocation::Named(name) => {
+        let flour = self
+          .mix
+          .iter_mut()
+          .find(|f| f.name.eq_ignore_ascii_case(name))
+          .ok_or_else(|| Error::UnknownFlour(name.clone()))?;
+        if flour.flour() < requested {
+          return Err(Error::InsufficientNamedFlour(flour.name.clone(), flour.flour(), requested));
+        }
+        flour.repurposed += requested;
+      }
+      Allocation::Own(blend) => {
+        for (f, displaced) in self.mix.iter_mut().zip(Self::shares(&mix_flour, requested, available)) {
+          f.displaced += displaced;
+        }
+        for (name, ratio) in blend {
+          let contributed = requested * *ratio;
+          match self.mix.iter_mut().find(|f| f.name.eq_ignore_ascii_case(name)) {
+            Some(flour) => flour.contributed += contributed,
+            None => {
+              let mut flour = Flour::new(name.clone(), Ratio::ZERO, &self.total_mass);
+              flour.contributed = contributed;
+              self.mix.push(flour);
+            }
+          }
+        }
+      }
+    }
+    Ok(self)
+  }
 
-    if q > Gram::ZERO {
-      Err(Error::InsufficientFlour(
-        *self.total_mass,
-        ingredient.flour(),
-      ))
-    } else {
-      Ok(Flours {
-        total_mass: self.total_mass,
-        mix,
+  /// Splits `requested` in proportion to what is left of each flour, `available` in total.
+  /// The last flour with anything left takes the rounding remainder
+  fn shares(left: &[Gram], requested: Gram, available: Gram) -> Vec<Gram> {
+    let last = left.iter().rposition(|f| *f > Gram::ZERO).unwrap_or(0);
+    let mut q = requested;
+    left
+      .iter()
+      .enumerate()
+      .map(|(i, f)| {
+        let share = if i == last {
+          q
+        } else {
+          Gram((requested.0 * f.0 / available.0).round_dp(2))
+        };
+        q = q - share;
+        share
       })
-    }
+      .collect()
   }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::assign_op_pattern)]
 mod tests {
   use crate::ingredient::preferment::Starter;
 
@@ -180,11 +295,11 @@ use super::*;
 
       let flours = random_sum(num_flours, 100)
         .iter()
-        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).into()));
+        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).try_into().unwrap()));
 
       let result = flours.total_ratio();
       assert_eq!(result.is_ok(), true);
-      assert_eq!(result.unwrap(), 100.into());
+      assert_eq!(result.unwrap(), 100.try_into().unwrap());
       Ok(())
     });
   }
@@ -202,13 +317,13 @@ use super::*;
       let range = random_sum(num_flours, sum);
       let flours = range
         .iter()
-        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).into()));
+        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).try_into().unwrap()));
 
       let result = flours.total_ratio();
-      assert_eq!(result.is_err(), true);
+      assert!(result.is_err());
       assert_eq!(
         result.unwrap_err(),
-        Error::InsufficientFLourRatios(sum.into())
+        Error::InsufficientFLourRatios(sum.try_into().unwrap())
       );
       Ok(())
     });
@@ -227,13 +342,13 @@ use super::*;
       let range = random_sum(num_flours, sum);
       let flours = range
         .iter()
-        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).into()));
+        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).try_into().unwrap()));
 
       let result = flours.total_ratio();
-      assert_eq!(result.is_err(), true);
+      assert!(result.is_err());
       assert_eq!(
         result.unwrap_err(),
-        Error::InsufficientFLourRatios(sum.into())
+        Error::InsufficientFLourRatios(sum.try_into().unwrap())
       );
       Ok(())
     });
@@ -248,8 +363,8 @@ use super::*;
       let mass = Rc::new(Gram(u.int_in_range(500..=5000)?.into()));
       let mut flours = Flours::new(&mass);
 
-      flours = flours.add_flour("f1".into(), 100.into());
-      let starter = Starter::new(&mass, portion.into(), 100.into());
+      flours = flours.add_flour("f1".into(), 100.try_into().unwrap());
+      let starter = Starter::new(&mass, portion.try_into().unwrap(), 100.try_into().unwrap());
 
       let result = flours.repurpose(&starter);
       assert_eq!(result.is_ok(), true);
@@ -277,9 +392,9 @@ use super::*;
       let range = random_sum(num_flours, 100);
       let flours = range
         .iter()
-        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).into()));
+        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).try_into().unwrap()));
 
-      let starter = Starter::new(&mass, portion.into(), 100.into());
+      let starter = Starter::new(&mass, portion.try_into().unwrap(), 100.try_into().unwrap());
 
       let result = flours.repurpose(&starter);
       assert_eq!(result.is_ok(), true);
@@ -297,6 +412,60 @@ use super::*;
     });
   }
 
+  impl Flours {
+    /// The (added, gross) flour of the named flour
+    fn mix_flour(&self, name: &str) -> Option<(Gram, Gram)> {
+      self.mix.iter().find(|f| f.name == name).map(|f| (f.flour(), f.gross_flour()))
+    }
+  }
+
+  fn white_and_rye(mass: &Rc<Gram>) -> Flours {
+    Flours::new(mass).add_flour("white".into(), 80.try_into().unwrap()).add_flour("rye".into(), 20.try_into().unwrap())
+  }
+
+  #[test]
+  fn proportional_repurpose_takes_from_every_flour() {
+    let mass = Rc::new(Gram::from(1000));
+    let starter = Starter::new(&mass, 20.try_into().unwrap(), 100.try_into().unwrap());
+    let flours = white_and_rye(&mass).repurpose_with(&starter, &Allocation::Proportional).unwrap();
+
+    assert_eq!(flours.mix[0].flour(), 640.into());
+    assert_eq!(flours.mix[1].flour(), 160.into());
+  }
+
+  #[test]
+  fn named_repurpose_takes_from_the_named_flour() {
+    let mass = Rc::new(Gram::from(1000));
+    let starter = Starter::new(&mass, 20.try_into().unwrap(), 100.try_into().unwrap());
+    let flours = white_and_rye(&mass).repurpose_with(&starter, &Allocation::named("RYE")).unwrap();
+    assert_eq!(flours.mix[0].flour(), 800.into());
+    assert_eq!(flours.mix[1].flour(), Gram::ZERO);
+
+    let starter = Starter::new(&mass, 25.try_into().unwrap(), 100.try_into().unwrap());
+    assert_eq!(
+      white_and_rye(&mass).repurpose_with(&starter, &Allocation::named("rye")).unwrap_err(),
+      Error::InsufficientNamedFlour("rye".to_owned(), 200.into(), 250.into())
+    );
+    assert_eq!(
+      white_and_rye(&mass).repurpose_with(&starter, &Allocation::named("spelt")).unwrap_err(),
+      Error::UnknownFlour("spelt".to_owned())
+    );
+  }
+
+  #[test]
+  fn own_flour_is_merged_into_the_formula() {
+    let mass = Rc::new(Gram::from(1000));
+    let starter = Starter::new(&mass, 20.try_into().unwrap(), 100.try_into().unwrap());
+    let blend = Allocation::Own(vec![("rye".to_owned(), 50.try_into().unwrap()), ("spelt".to_owned(), 50.try_into().unwrap())]);
+    let flours = white_and_rye(&mass).repurpose_with(&starter, &blend).unwrap();
+
+    assert_eq!(flours.mix.len(), 3);
+    assert_eq!(flours.mix_flour("white"), Some((640.into(), 640.into())));
+    assert_eq!(flours.mix_flour("rye"), Some((160.into(), 260.into())));
+    assert_eq!(flours.mix_flour("spelt"), Some((Gram::ZERO, 100.into())));
+    assert_eq!(flours.flour() + starter.flour(), *mass);
+  }
+
   /// Test the invariant
   ///   <Added flour> + <preferment flour> = <Total flour>
   #[test]
@@ -311,10 +480,10 @@ use super::*;
       let range = random_sum(num_flours, 100);
       let flours = range
         .iter()
-        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).into()));
+        .fold(flours, |fs, i| fs.add_flour("f".into(), (*i).try_into().unwrap()));
 
-      let starter = Starter::new(&mass, portion1.into(), 100.into());
-      let tangzhong = Starter::new(&mass, portion2.into(), 100.into());
+      let starter = Starter::new(&mass, portion1.try_into().unwrap(), 100.try_into().unwrap());
+      let tangzhong = Starter::new(&mass, portion2.try_into().unwrap(), 100.try_into().unwrap());
 
       let result = flours.repurpose(&starter);
       assert_eq!(result.is_ok(), true);
@@ -1,6 +1,9 @@
 use prettytable::Table;
+use serde::Serialize;
 
 use crate::common::mass::*;
+use crate::fermentation::Leavening;
+use crate::ingredient::preferment::Portion;
 
 use std::fmt::Debug;
 
@@ -32,4 +35,93 @@ pub trait Ingredient: Debug {
   fn describe(&self, table: Table, _: Gram) -> Table {
     table
   }
+
+  /// The mass of the ingredient called `name`, if it is this ingredient or one of its parts
+  fn mass_of(&self, _name: &str) -> Option<Gram> {
+    None
+  }
+
+  /// The mass of the named enrichment already in this ingredient (e.g. the salt in old dough),
+  /// to be taken off the enrichment added to the final dough
+  fn carries(&self, _name: &str) -> Gram {
+    Gram::ZERO
+  }
+
+  /// The water held by the ingredient (e.g. soaked grains), part of the dough but not free to hydrate the flour
+  fn bound(&self) -> Gram {
+    Gram::ZERO
+  }
+
+  /// The mass made along with the ingredient but kept out of the dough (e.g. the starter kept as the next seed)
+  fn kept(&self) -> Gram {
+    Gram::ZERO
+  }
+
+  /// What the ingredient raises the dough with (e.g. a starter's culture or commercial yeast), if anything
+  fn leavening(&self) -> Option<Leavening> {
+    None
+  }
+
+  /// The same ingredient with its flour as `portion` of the total flour (e.g. a starter solved for a schedule),
+  /// None when its flour isn't a portion of the total flour
+  fn with_portion(&self, _portion: Portion) -> Option<Box<dyn Ingredient>> {
+    None
+  }
+
+  /// The machine readable counterpart of `describe`
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary;
+}
+
+/// A structured description of an ingredient (or of an ingredient's component)
+/// as it appears in the recipe.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+  pub name: String,
+  pub flour: Gram,
+  pub water: Gram,
+  pub other: Gram,
+  pub total: Gram,
+
+  /// The ingredient's total mass as percent of the total flour mass
+  pub percent_flour: Ratio,
+
+  /// The ingredient's total mass as percent of the dough's mass
+  pub percent_total: Ratio,
+
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub comment: String,
+
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub components: Vec<Summary>,
+}
+
+impl Summary {
+  pub fn new(name: &str, flour: Gram, water: Gram, other: Gram, total_flour: Gram, total: Gram) -> Self {
+    let mass = flour + water + other;
+    Summary {
+      name: name.to_lowercase(),
+      flour,
+      water,
+      other,
+      total: mass,
+      percent_flour: mass.as_ratio_of(&total_flour),
+      percent_total: mass.as_ratio_of(&total),
+      comment: String::new(),
+      components: vec![],
+    }
+  }
+
+  pub fn of(name: &str, ingredient: &dyn Ingredient, total_flour: Gram, total: Gram) -> Self {
+    Self::new(name, ingredient.flour(), ingredient.water(), ingredient.other(), total_flour, total)
+  }
+
+  pub fn with_comment(mut self, comment: String) -> Self {
+    self.comment = comment;
+    self
+  }
+
+  pub fn with_components(mut self, components: Vec<Summary>) -> Self {
+    self.components = components;
+    self
+  }
 }
@@ -1,12 +1,17 @@
 pub mod flour;
+#[allow(clippy::module_inception)]
 pub mod ingredient;
 pub mod water;
 pub mod salt;
 pub mod enrichment;
+pub mod soaker;
+pub mod yeast;
 
 pub mod preferment;
 
-pub use crate::ingredient::ingredient::Ingredient;
+pub use crate::ingredient::ingredient::{Ingredient, Summary};
 pub use crate::ingredient::water::Water;
 pub use crate::ingredient::enrichment::Enrichment;
 pub use crate::ingredient::salt::SaltPercentage;
+pub use crate::ingredient::soaker::Soaker;
+pub use crate::ingredient::yeast::{Yeast, YeastRatio, YeastType};
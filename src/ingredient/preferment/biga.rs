@@ -0,0 +1,160 @@
+use std::rc::Rc;
+
+use super::preferment::*;
+use crate::common::mass::Ratio;
+use crate::fermentation::Leavening;
+use crate::{common::{Field, Gram}, ingredient::{Ingredient, Summary}, Result};
+
+use prettytable::{row, Table};
+
+/// Biga is the Italian stiff preferment, flour with just enough water to form a shaggy mass (45%-60% hydration)
+/// and a little commercial yeast, fermented for 12-48 hours at cool temperatures.
+/// It lends the dough strength and a complex, slightly acidic flavor, and is traditional in ciabatta and pizza.
+///
+/// Like a `Starter` a biga is described by
+/// - A Flour content as a percent of the total flour content
+/// - Hydration percent (45% to 60%)
+/// - Yeast as percent of the biga's flour (Usually 0.5% instant yeast)
+///
+#[derive(Debug)]
+pub struct Biga {
+  total_flour: Rc<Gram>,
+  portion: Portion,
+  hydration: BigaHydration,
+  yeast: YeastPercent,
+}
+
+impl Biga {
+  /// Yeast as percent of the biga's flour, when not given
+  pub const YEAST: f32 = 0.5;
+
+  pub fn new(total_flour: &Rc<Gram>, portion: PortionPercent, hydration: BigaHydration, yeast: YeastPercent) -> Self {
+    Biga {
+      total_flour: total_flour.clone(),
+      portion: portion.into(),
+      hydration,
+      yeast,
+    }
+  }
+
+  pub fn build(portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description =
+      args.description().specialize("biga", "biga:<ratio>:<hydration>[:<yeast>]", "biga:30:50:0.5");
+    let args = args.rebase(&description).split(':', &["hydration", "yeast"], 1)?;
+    let hydration = &args[0];
+    let yeast = match args.get(1) {
+      Some(yeast) => yeast.percent()?,
+      None => YeastPercent::checked(Self::YEAST)?,
+    };
+    let hydration = hydration.percent()?;
+    Ok(Box::new(Biga { total_flour: total_mass.clone(), portion, hydration, yeast }))
+  }
+
+  fn comment(&self) -> String {
+    format!("{} Hydration, {} of total flour, {} yeast", self.hydration, self.portion, self.yeast)
+  }
+}
+
+impl Ingredient for Biga {
+  fn water(&self) -> Gram {
+    self.flour() * self.hydration
+  }
+
+  fn flour(&self) -> Gram {
+    *self.total_flour * self.portion
+  }
+
+  /// The yeast
+  fn other(&self) -> Gram {
+    self.flour() * self.yeast
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let flour_ratio_flour: Ratio = self.flour().as_ratio_of(&self.total_flour);
+    let yeast_ratio_flour: Ratio = self.other().as_ratio_of(&self.total_flour);
+
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
+    let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
+    let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
+    let yeast_ratio_total: Ratio = self.other().as_ratio_of(&total);
+
+    let comment = self.comment();
+    table.add_row(row![lb -> "BIGA", "",            rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
+    table.add_row(row!["",             l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total]);
+    table.add_row(row!["",             l -> "water",  r   -> self.water(),  c -> "", r -> water_ratio_total]);
+    table.add_row(row!["",             l -> "yeast",  r   -> self.other(),  r -> yeast_ratio_flour, r -> yeast_ratio_total]);
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("biga", self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(vec![
+        Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+        Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+        Summary::new("yeast", Gram::ZERO, Gram::ZERO, self.other(), total_flour, total),
+      ])
+  }
+
+  fn leavening(&self) -> Option<Leavening> {
+    Some(Leavening::Preferment { flour: self.flour(), yeast: self.other() })
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("biga").then(|| self.total())
+  }
+
+  fn carries(&self, name: &str) -> Gram {
+    if name.eq_ignore_ascii_case("yeast") {
+      self.other()
+    } else {
+      Gram::ZERO
+    }
+  }
+}
+
+impl Preferment for Biga {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ingredient::preferment::BUILDER;
+  use crate::error::diagnostics;
+  use arbtest::arbtest;
+
+  #[test]
+  fn valid_ratio_to_mass() {
+    arbtest(|u| {
+      let total_flour: Rc<Gram> = Rc::new(u.int_in_range(10..=5000)?.into());
+      let portion: PortionPercent = u.int_in_range(PortionPercent::range())?.try_into().unwrap();
+      let hydration: BigaHydration = u.int_in_range(BigaHydration::range())?.try_into().unwrap();
+      let yeast: YeastPercent = u.int_in_range(YeastPercent::range())?.try_into().unwrap();
+
+      let biga = Biga::new(&total_flour, portion, hydration, yeast);
+      let biga_flour = *total_flour * portion;
+
+      assert_eq!(biga.flour(), biga_flour);
+      assert_eq!(biga.water(), biga_flour * hydration);
+      assert_eq!(biga.other(), biga_flour * yeast);
+      assert_eq!(biga.total(), biga.flour() + biga.water() + biga.other());
+
+      Ok(())
+    });
+  }
+
+  #[test]
+  fn hydration_is_stiff() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let biga = BUILDER.get("biga:20:45", &total_flour).unwrap();
+    assert_eq!(biga.flour(), 200.into());
+    assert_eq!(biga.water(), 90.into());
+    assert_eq!(biga.other(), 1.into());
+
+    let error = diagnostics(BUILDER.get("biga:20:100", &total_flour).unwrap_err());
+    assert_eq!(error[0].message, "100% is out of range, hydration is 45% to 60%");
+
+    // Only a biga is that stiff
+    let error = diagnostics(BUILDER.get("starter:10:45", &total_flour).unwrap_err());
+    assert_eq!(error[0].message, "45% is out of range, hydration is 50% to 500%");
+  }
+}
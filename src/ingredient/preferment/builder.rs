@@ -1,45 +1,270 @@
 use crate::{
-  common::Gram,
-  ingredient::{preferment::{Starter, Tangzhong}, Ingredient},
+  common::{mass::Ratio, percent::PERCENT, Description, Field, Gram},
+  ingredient::{
+    flour::Allocation,
+    preferment::{Biga, CustomPreferment, PateFermentee, Portion, PortionPercent, Poolish, PrefermentType, PrefermentTypes, SolvedPortion, Sponge, Starter, Tangzhong, Yudane},
+    Ingredient,
+  },
   Error, Result,
 };
 use lazy_static::lazy_static;
-use std::{collections::HashMap, rc::Rc};
+use rust_decimal::prelude::*;
+use std::{
+  collections::HashMap,
+  rc::Rc,
+  sync::{Arc, RwLock},
+};
 
-type StringToBuilder = fn(String, &Rc<Gram>) -> Result<Box<dyn Ingredient>>;
+type StringToBuilder = fn(Portion, Field, &Rc<Gram>) -> Result<Box<dyn Ingredient>>;
 pub struct Builder {
   builders: HashMap<String, StringToBuilder>,
+
+  /// Preferment types defined in configuration files, see `register`
+  custom: RwLock<HashMap<String, Arc<PrefermentType>>>,
 }
 
 impl Builder {
   fn new(builders: Vec<(String, StringToBuilder)>) -> Self {
     Builder {
       builders: builders.into_iter().collect(),
+      custom: RwLock::new(HashMap::new()),
+    }
+  }
+
+  /// A builder of the built in preferments only
+  pub(crate) fn built_in() -> Self {
+    Builder::new(
+      vec!(
+        ("starter".to_owned(), Starter::build),
+        ("poolish".to_owned(), Poolish::build),
+        ("biga".to_owned(), Biga::build),
+        ("pate".to_owned(), PateFermentee::build),
+        ("sponge".to_owned(), Sponge::build),
+        ("tangzhong".to_owned(), Tangzhong::build),
+        ("yudane".to_owned(), Yudane::build),
+      )
+    )
+  }
+
+  /// Adds the preferment `types` to the supported ones, replacing earlier types of the same name.
+  /// The built in preferments can't be replaced
+  pub fn register(&self, types: PrefermentTypes) -> Result<()> {
+    if let Some(kind) = types.preferment.iter().find(|kind| self.builders.contains_key(&kind.name.to_lowercase())) {
+      return Err(Error::InvalidConfigFile(format!("preferment '{}' is built in", kind.name)));
+    }
+
+    let mut custom = self.custom.write().unwrap_or_else(|e| e.into_inner());
+    for kind in types.preferment {
+      custom.insert(kind.name.to_lowercase(), Arc::new(kind));
+    }
+    Ok(())
+  }
+
+  fn custom(&self, id: &str) -> Option<Arc<PrefermentType>> {
+    self.custom.read().unwrap_or_else(|e| e.into_inner()).get(id).cloned()
+  }
+
+  /// The names of the supported preferments, sorted
+  pub fn names(&self) -> Vec<String> {
+    let custom = self.custom.read().unwrap_or_else(|e| e.into_inner());
+    let mut names: Vec<String> = self.builders.keys().chain(custom.keys()).cloned().collect();
+    names.sort();
+    names
+  }
+
+  /// The supported preferment with the name closest to `id`, if any is close enough to be a typo
+  fn closest(&self, id: &str) -> Option<String> {
+    self
+      .names()
+      .into_iter()
+      .map(|name| (edit_distance(&name, id), name))
+      .filter(|(distance, _)| *distance <= 2)
+      .min()
+      .map(|(_, name)| name)
+  }
+
+  /// The preferment's own flours `<flour>:<ratio>,...`, adding up to 100% of its flour
+  fn blend(blend: Field) -> Result<Allocation> {
+    let flours = blend
+      .split_all(',', "flour")
+      .iter()
+      .map(|flour| {
+        let (name, ratio) = flour.split_once(':', "flour", "ratio")?;
+        Ok((name.name()?, ratio.percent::<0, 100, 2>()?.rebound()?))
+      })
+      .collect::<Result<Vec<(String, Ratio)>>>()?;
+
+    let total: Ratio = flours.iter().map(|(_, ratio)| *ratio).sum();
+    if total != Ratio::try_from(100)? {
+      return Err(blend.error_with_help(
+        format!("adds up to {}", total),
+        "the preferment's own flours are percent of its flour, and must add up to 100%".to_owned(),
+      ));
     }
+    Ok(Allocation::Own(flours))
+  }
+
+  /// The preferment described by `desc`, e.g. `starter:10:100`
+  pub fn get(&self, desc: &str, total_flour: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    self.allocated(desc, total_flour).map(|(preferment, _)| preferment)
   }
 
-  /// Returns (ID, ARGS) pair extracted from the user's prefermant description
-  fn extract_args(desc: &String) -> Result<(String, String)> {
-    match desc.split_once(':') {
-      Some((id, args)) => Ok((id.to_owned(), args.to_owned())),
-      _ => Err(Error::InvalidPrefermentArgs(desc.clone())),
+  /// The preferment described by `desc` and the flours its flour is taken from, e.g. `starter:10:100@rye`.
+  /// The preferment may be given by its mass rather than its flour's portion, e.g. `starter@150g:100`
+  pub fn allocated(&self, desc: &str, total_flour: &Rc<Gram>) -> Result<(Box<dyn Ingredient>, Allocation)> {
+    let description = Description::new(
+      "preferment",
+      desc,
+      "<name>:<ratio>:<args> or <name>@<mass>:<args>, then [@<flour>|@all|=<flour>:<ratio>,...]",
+      "starter:10:100@rye",
+    );
+    let whole = description.whole("preferment");
+    let allocation = whole
+      .rsplit_once('@', "preferment", "flour")
+      .ok()
+      .filter(|(preferment, _)| preferment.text().contains(':'));
+    let (preferment, allocation) = if let Ok((preferment, blend)) = whole.split_once('=', "preferment", "blend") {
+      (preferment, Self::blend(blend)?)
+    } else if let Some((preferment, flour)) = allocation {
+      (preferment, Allocation::named(&flour.name()?))
+    } else {
+      (whole, Allocation::InOrder)
+    };
+
+    let (id, args) = preferment.split_first(':', "name", "args");
+    let (id, mass) = match id.split_once('@', "name", "mass") {
+      Ok((id, mass)) => (id, Some(mass)),
+      Err(_) => (id, None),
+    };
+    let name = id.text().to_lowercase();
+    let custom = self.custom(&name);
+    let build = |portion: Portion, args: Field| match (self.builders.get(&name), &custom) {
+      (Some(builder), _) => builder(portion, args, total_flour),
+      (None, Some(kind)) => CustomPreferment::build(kind, portion, args, total_flour),
+      (None, None) => {
+        let known = format!("supported preferments are {}", self.names().join(", "));
+        let help = match self.closest(&name) {
+          Some(name) => format!("did you mean '{}'? {}", name, known),
+          None => known,
+        };
+        Err(id.error_with_help(format!("'{}' is not supported", id.text()), help))
+      }
+    };
+
+    let (portion, portion_field, args) = match mass {
+      Some(mass) => (Self::portion(&build, &mass, args.clone(), total_flour)?.into(), mass, args),
+      None => {
+        let (ratio, args) = args.split_first(':', "ratio", "args");
+        let portion: PortionPercent = ratio.percent()?;
+        (portion.into(), ratio, args)
+      }
+    };
+    let preferment = build(portion, args)?;
+    if let Some(kind) = custom {
+      kind.check_portion(portion, &portion_field)?;
     }
+    Ok((preferment, allocation))
+  }
+
+  /// The portion at which the preferment weights `mass`, a preferment's mass is linear in its portion
+  fn portion<'a>(
+    build: &impl Fn(Portion, Field<'a>) -> Result<Box<dyn Ingredient>>,
+    mass: &Field,
+    args: Field<'a>,
+    total_flour: &Rc<Gram>,
+  ) -> Result<SolvedPortion> {
+    let target = mass.mass()?;
+    let sample = Portion::from(PortionPercent::try_from(10)?);
+    let sample_mass = build(sample, args)?.total();
+    let portion = sample.as_decimal() * PERCENT * target.0 / sample_mass.0;
+
+    let portion = portion.to_f32().unwrap_or(f32::MAX);
+    SolvedPortion::checked(portion).map_err(|_| {
+      mass.error(format!(
+        "{} needs {:.2}% of the {} total flour, a preferment takes {}% to {}%",
+        target,
+        portion,
+        total_flour,
+        SolvedPortion::range().start(),
+        SolvedPortion::range().end(),
+      ))
+    })
   }
+}
 
-  pub fn get(&self, desc: &String, total_flour: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
-    let (id, args) = Self::extract_args(desc)?;
-    match self.builders.get(&id.to_lowercase()) {
-      Some(builder) => builder(args, total_flour),
-      _ => Err(Error::InvalidPreferment(id)),
+/// The number of single character edits turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let substitution = diagonal + usize::from(ca != *cb);
+      diagonal = row[j + 1];
+      row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
     }
   }
+  row[b.len()]
 }
 
 lazy_static! {
-  pub static ref BUILDER: Builder = Builder::new(
-    vec!(
-      ("starter".to_owned(), Starter::build),
-      ("tangzhong".to_owned(), Tangzhong::build),
-    )
-  );
-}
\ No newline at end of file
+  pub static ref BUILDER: Builder = Builder::built_in();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn own_flour_blend() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let (_, allocation) = BUILDER.allocated("starter:10:100=rye:50,wholewheat:50", &total_flour).unwrap();
+    assert_eq!(
+      allocation,
+      Allocation::Own(vec![("rye".to_owned(), 50.try_into().unwrap()), ("wholewheat".to_owned(), 50.try_into().unwrap())])
+    );
+
+    let error = diagnostics(BUILDER.allocated("starter:10:100=rye:50,wholewheat:40", &total_flour).unwrap_err());
+    assert_eq!((error[0].message.as_str(), error[0].span.clone()), ("adds up to 90.00%", 15..35));
+  }
+
+  #[test]
+  fn preferment_by_mass() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let starter = BUILDER.get("starter@150g:100", &total_flour).unwrap();
+    assert_eq!(starter.flour(), 75.into());
+    assert_eq!(starter.total(), 150.into());
+
+    let (poolish, allocation) = BUILDER.allocated("poolish@201:100:0.5@rye", &total_flour).unwrap();
+    // The portion is rounded to 2 decimals
+    assert!((poolish.total() - Gram::from(201)).0.abs() < Decimal::ONE);
+    assert_eq!(allocation, Allocation::named("rye"));
+
+    let error = diagnostics(BUILDER.get("starter@800g:100", &total_flour).unwrap_err());
+    assert_eq!(error[0].span, 8..12);
+    assert_eq!(
+      error[0].message,
+      "800.00 g needs 40.00% of the 1000.00 g total flour, a preferment takes 1% to 30%"
+    );
+  }
+
+  #[test]
+  fn only_a_portion_by_mass_is_solved_to_decimals() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let described = BUILDER.get("starter:10:100", &total_flour).unwrap();
+    assert!(described.summary(1000.into(), 1700.into()).comment.contains("10% of total flour"));
+
+    let solved = BUILDER.get("starter@201g:100", &total_flour).unwrap();
+    assert!(solved.summary(1000.into(), 1700.into()).comment.contains("10.05% of total flour"));
+  }
+
+  #[test]
+  fn unknown_preferment_suggests_the_closest() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let error = diagnostics(BUILDER.get("startr:10:100", &total_flour).unwrap_err());
+    assert_eq!(error[0].span, 0..6);
+    assert!(error[0].help.starts_with("did you mean 'starter'?"));
+  }
+}
@@ -0,0 +1,417 @@
+use std::{fs, ops::RangeInclusive, path::Path, rc::Rc, sync::Arc};
+
+use super::preferment::*;
+use crate::common::mass::Ratio;
+use crate::fermentation::Leavening;
+use crate::common::percent::PERCENT;
+use crate::{
+  common::{Field, Gram},
+  ingredient::{Ingredient, Summary},
+  Error, Result,
+};
+
+use prettytable::{row, Table};
+use rust_decimal::prelude::*;
+use serde::Deserialize;
+
+/// Preferment types defined in a configuration file rather than in code
+///
+/// ```toml
+/// [[preferment]]
+/// name = "madre"
+/// label = "Lievito madre"
+/// culture = "sourdough"
+/// hydration = 50
+/// portion_range = [10, 25]
+/// hydration_range = [50, 60]
+///
+/// [[preferment.components]]
+/// name = "sugar"
+/// default = 1
+/// max = 5
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrefermentTypes {
+  #[serde(default)]
+  pub preferment: Vec<PrefermentType>,
+}
+
+/// A preferment type, flour and water and any number of extra components
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrefermentType {
+  /// The name it is described by, e.g. `madre` in `madre:20`
+  pub name: String,
+
+  /// The name it is displayed by, the upper cased `name` when not given
+  pub label: Option<String>,
+
+  /// The culture it is started with, the fermentation is estimated and scheduled by it
+  pub culture: Option<PrefermentCulture>,
+
+  /// Hydration percent when not given
+  pub hydration: f32,
+
+  /// The allowed portion of the total flour, within 1% to 30%
+  pub portion_range: Option<[f32; 2]>,
+
+  /// The allowed hydration, within 50% to 500%
+  pub hydration_range: Option<[f32; 2]>,
+
+  #[serde(default)]
+  pub components: Vec<Component>,
+}
+
+/// What a preferment type is started with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrefermentCulture {
+  /// A sourdough culture, e.g. a lievito madre
+  Sourdough,
+
+  /// Commercial yeast, given as its `yeast` component
+  Yeast,
+}
+
+/// A component other than flour and water, e.g. yeast or salt, as percent of the preferment's flour
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Component {
+  pub name: String,
+
+  /// Percent of the preferment's flour when not given
+  pub default: f32,
+
+  /// The most that may be given, 100% when not given
+  pub max: Option<f32>,
+}
+
+impl PrefermentTypes {
+  pub fn from_toml(content: &str) -> Result<Self> {
+    let types: PrefermentTypes = toml::from_str(content).map_err(|e| Error::InvalidConfigFile(e.to_string()))?;
+    for kind in &types.preferment {
+      kind.validate()?;
+    }
+    Ok(types)
+  }
+
+  pub fn load(path: &Path) -> Result<Self> {
+    let content =
+      fs::read_to_string(path).map_err(|e| Error::ConfigFileAccess(path.display().to_string(), e.to_string()))?;
+    Self::from_toml(&content)
+  }
+}
+
+impl PrefermentType {
+  fn invalid(&self, message: String) -> Error {
+    Error::InvalidConfigFile(format!("preferment '{}' {}", self.name, message))
+  }
+
+  /// Checks `range` is within `bounds` and holds `value`, when given
+  fn validate_range(&self, what: &str, range: [f32; 2], bounds: [f32; 2], value: Option<f32>) -> Result<()> {
+    let [min, max] = range;
+    if min > max || min < bounds[0] || max > bounds[1] {
+      return Err(self.invalid(format!(
+        "{} range {}% to {}% is not within {}% to {}%",
+        what, min, max, bounds[0], bounds[1]
+      )));
+    }
+    match value {
+      Some(value) if value < min || value > max => {
+        Err(self.invalid(format!("{} {}% is out of its range {}% to {}%", what, value, min, max)))
+      }
+      _ => Ok(()),
+    }
+  }
+
+  fn validate(&self) -> Result<()> {
+    if self.name.trim().is_empty() || self.name.contains([':', '@', '=']) {
+      return Err(self.invalid("is not a valid name".to_owned()));
+    }
+
+    let portion = self.portion_range.unwrap_or(bounds(PortionPercent::range()));
+    self.validate_range("portion", portion, bounds(PortionPercent::range()), None)?;
+    self.validate_range("hydration", self.hydration_range(), bounds(HydrationPercent::range()), Some(self.hydration))?;
+
+    let yeast = self.components.iter().any(|c| c.name.eq_ignore_ascii_case("yeast"));
+    if self.culture == Some(PrefermentCulture::Yeast) && !yeast {
+      return Err(self.invalid("is started with yeast but has no yeast component".to_owned()));
+    }
+    for component in &self.components {
+      if component.name.trim().is_empty() {
+        return Err(self.invalid("has a component with no name".to_owned()));
+      }
+      self.validate_range(&component.name, [0.0, component.max()], [0.0, 100.0], Some(component.default))?;
+    }
+    Ok(())
+  }
+
+  fn hydration_range(&self) -> [f32; 2] {
+    self.hydration_range.unwrap_or(bounds(HydrationPercent::range()))
+  }
+
+  fn label(&self) -> String {
+    self.label.clone().unwrap_or_else(|| self.name.clone())
+  }
+
+  fn syntax(&self) -> String {
+    let optional = std::iter::once("hydration")
+      .chain(self.components.iter().map(|c| c.name.as_str()))
+      .fold(String::new(), |syntax, name| format!("[:<{}>{}]", name, syntax));
+    format!("{}:<ratio>{}", self.name, optional)
+  }
+
+  fn example(&self) -> String {
+    let portion = self.portion_range.map(|[min, _]| min.max(10.0)).unwrap_or(10.0);
+    format!("{}:{}:{}", self.name, portion, self.hydration)
+  }
+
+  /// A percent of `field` within `range`, `name` is what it is
+  fn percent(field: &Field, name: &str, [min, max]: [f32; 2]) -> Result<f32> {
+    let value = field.number::<f32>()?;
+    if value < min || value > max {
+      return Err(field.error(format!("{}% is out of range, {} is {}% to {}%", value, name, min, max)));
+    }
+    Ok(value)
+  }
+
+  /// Checks the portion given by `field` is within the type's own portion range
+  pub(crate) fn check_portion(&self, portion: Portion, field: &Field) -> Result<()> {
+    let value = (portion.as_decimal() * PERCENT).to_f32().unwrap_or(f32::MAX);
+    match self.portion_range {
+      Some([min, max]) if value < min || value > max => Err(field.error(format!(
+        "{}% is out of range, {} portion is {}% to {}%",
+        value, self.name, min, max
+      ))),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// The bounds of a `Percent` range
+fn bounds(range: RangeInclusive<u32>) -> [f32; 2] {
+  [*range.start() as f32, *range.end() as f32]
+}
+
+impl Component {
+  fn max(&self) -> f32 {
+    self.max.unwrap_or(100.0)
+  }
+}
+
+/// A preferment of a type defined in a configuration file
+#[derive(Debug)]
+pub struct CustomPreferment {
+  kind: Arc<PrefermentType>,
+  total_flour: Rc<Gram>,
+  portion: Portion,
+  hydration: HydrationPercent,
+  components: Vec<(String, Ratio)>,
+}
+
+impl CustomPreferment {
+  pub fn build(kind: &Arc<PrefermentType>, portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description = args.description().specialize(&kind.label(), &kind.syntax(), &kind.example());
+    let args = args.rebase(&description);
+    let (hydration, components) = args.split_first(':', "hydration", "components");
+
+    let hydration = match hydration.text() {
+      "" => kind.hydration,
+      _ => PrefermentType::percent(&hydration, "hydration", kind.hydration_range())?,
+    };
+
+    let given = if components.text().is_empty() { vec![] } else { components.split_all(':', "component") };
+    if given.len() > kind.components.len() {
+      return Err(components.error(format!("expected at most {} components, got {}", kind.components.len(), given.len())));
+    }
+    let components = kind
+      .components
+      .iter()
+      .enumerate()
+      .map(|(n, component)| {
+        let value = match given.get(n) {
+          Some(field) => PrefermentType::percent(field, &component.name, [0.0, component.max()])?,
+          None => component.default,
+        };
+        Ok((component.name.clone(), Ratio::checked(value)?))
+      })
+      .collect::<Result<Vec<(String, Ratio)>>>()?;
+
+    Ok(Box::new(CustomPreferment {
+      kind: kind.clone(),
+      total_flour: total_mass.clone(),
+      portion,
+      hydration: HydrationPercent::checked(hydration)?,
+      components,
+    }))
+  }
+
+  fn component(&self, ratio: Ratio) -> Gram {
+    self.flour() * ratio
+  }
+
+  fn comment(&self) -> String {
+    self.components.iter().fold(
+      format!("{} Hydration, {} of total flour", self.hydration, self.portion),
+      |comment, (name, ratio)| format!("{}, {} {}", comment, ratio, name),
+    )
+  }
+}
+
+impl Ingredient for CustomPreferment {
+  fn water(&self) -> Gram {
+    self.flour() * self.hydration
+  }
+
+  fn flour(&self) -> Gram {
+    *self.total_flour * self.portion
+  }
+
+  /// All the components
+  fn other(&self) -> Gram {
+    self.components.iter().fold(Gram::ZERO, |mass, (_, ratio)| mass + self.component(*ratio))
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let flour_ratio_flour: Ratio = self.flour().as_ratio_of(&self.total_flour);
+
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
+    let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
+    let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
+
+    let comment = self.comment();
+    table.add_row(row![lb -> self.kind.label().to_uppercase(), "", rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
+    table.add_row(row!["",             l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total]);
+    table.add_row(row!["",             l -> "water",  r   -> self.water(),  c -> "", r -> water_ratio_total]);
+    for (name, ratio) in &self.components {
+      let mass = self.component(*ratio);
+      let mass_ratio_flour: Ratio = mass.as_ratio_of(&self.total_flour);
+      let mass_ratio_total: Ratio = mass.as_ratio_of(&total);
+      table.add_row(row!["",           l -> name,     r   -> mass,          r -> mass_ratio_flour, r -> mass_ratio_total]);
+    }
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    let components = [
+      Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+      Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+    ];
+    let extra = self
+      .components
+      .iter()
+      .map(|(name, ratio)| Summary::new(name, Gram::ZERO, Gram::ZERO, self.component(*ratio), total_flour, total));
+
+    Summary::of(&self.kind.label(), self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(components.into_iter().chain(extra).collect())
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case(&self.kind.name).then(|| self.total())
+  }
+
+  /// A custom preferment leavens the dough by its culture, or when it carries yeast
+  fn leavening(&self) -> Option<Leavening> {
+    if self.kind.culture == Some(PrefermentCulture::Sourdough) {
+      return Some(Leavening::Sourdough(self.flour()));
+    }
+    let yeast = self.carries("yeast");
+    (yeast > Gram::ZERO).then(|| Leavening::Preferment { flour: self.flour(), yeast })
+  }
+
+  fn with_portion(&self, portion: Portion) -> Option<Box<dyn Ingredient>> {
+    Some(Box::new(CustomPreferment {
+      kind: self.kind.clone(),
+      total_flour: self.total_flour.clone(),
+      portion,
+      hydration: self.hydration,
+      components: self.components.clone(),
+    }))
+  }
+
+  fn carries(&self, name: &str) -> Gram {
+    self
+      .components
+      .iter()
+      .filter(|(component, _)| component.eq_ignore_ascii_case(name))
+      .fold(Gram::ZERO, |mass, (_, ratio)| mass + self.component(*ratio))
+  }
+}
+
+impl Preferment for CustomPreferment {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+  use crate::ingredient::preferment::builder::Builder;
+
+  const MADRE: &str = r#"
+    [[preferment]]
+    name = "madre"
+    label = "Lievito madre"
+    culture = "sourdough"
+    hydration = 50
+    portion_range = [10, 25]
+    hydration_range = [50, 60]
+
+    [[preferment.components]]
+    name = "sugar"
+    default = 1
+    max = 5
+  "#;
+
+  #[test]
+  fn resolved_alongside_the_built_in_preferments() {
+    let builder = Builder::built_in();
+    builder.register(PrefermentTypes::from_toml(MADRE).unwrap()).unwrap();
+    let total_flour = Rc::new(Gram::from(1000));
+
+    let madre = builder.get("madre:20", &total_flour).unwrap();
+    assert_eq!(madre.flour(), 200.into());
+    assert_eq!(madre.water(), 100.into());
+    assert_eq!(madre.other(), 2.into());
+    assert_eq!(madre.carries("sugar"), 2.into());
+    assert_eq!(madre.mass_of("madre"), Some(302.into()));
+
+    let madre = builder.get("madre:20:60:2", &total_flour).unwrap();
+    assert_eq!(madre.water(), 120.into());
+    assert_eq!(madre.other(), 4.into());
+    assert!(builder.names().contains(&"madre".to_owned()));
+    assert_eq!(madre.leavening(), Some(Leavening::Sourdough(200.into())));
+
+    let madre = madre.with_portion(PortionPercent::try_from(15).unwrap().into()).unwrap();
+    assert_eq!((madre.flour(), madre.water(), madre.carries("sugar")), (150.into(), 90.into(), 3.into()));
+    assert_eq!(madre.summary(1000.into(), 1700.into()).name, "lievito madre");
+  }
+
+  #[test]
+  fn its_ranges_are_enforced() {
+    let builder = Builder::built_in();
+    builder.register(PrefermentTypes::from_toml(MADRE).unwrap()).unwrap();
+    let total_flour = Rc::new(Gram::from(1000));
+
+    let error = diagnostics(builder.get("madre:5", &total_flour).unwrap_err()).remove(0);
+    assert_eq!((error.field.as_str(), error.span), ("ratio", 6..7));
+
+    let error = diagnostics(builder.get("madre:20:70", &total_flour).unwrap_err()).remove(0);
+    assert_eq!((error.field.as_str(), error.span), ("hydration", 9..11));
+
+    let error = diagnostics(builder.get("madre:20:50:8", &total_flour).unwrap_err()).remove(0);
+    assert_eq!(error.message, "8% is out of range, sugar is 0% to 5%");
+  }
+
+  #[test]
+  fn invalid_types_are_rejected() {
+    let out_of_range = MADRE.replace("hydration = 50", "hydration = 80");
+    assert!(matches!(PrefermentTypes::from_toml(&out_of_range), Err(Error::InvalidConfigFile(_))));
+
+    let no_yeast = MADRE.replace("\"sourdough\"", "\"yeast\"");
+    assert!(matches!(PrefermentTypes::from_toml(&no_yeast), Err(Error::InvalidConfigFile(_))));
+
+    let built_in = PrefermentTypes::from_toml(&MADRE.replace("\"madre\"", "\"starter\"")).unwrap();
+    assert!(matches!(Builder::built_in().register(built_in), Err(Error::InvalidConfigFile(_))));
+  }
+}
@@ -1,4 +1,8 @@
+mod biga;
 mod builder;
+mod custom;
+mod pate;
+mod poolish;
 /// A preferment is a portion of dough or a mixture of flour and water that is fermented
 /// before being added to the final dough. It's used to enhance flavor, texture, and
 /// aroma in breads, pastries, and other baked goods.
@@ -12,10 +16,20 @@ mod builder;
 ///   yudane
 ///   songe
 ///
+#[allow(clippy::module_inception)]
 mod preferment;
+mod sponge;
 mod starter;
 mod tangzhong;
+mod yudane;
 
+pub use crate::ingredient::preferment::biga::Biga;
 pub use crate::ingredient::preferment::builder::BUILDER;
+pub use crate::ingredient::preferment::custom::{Component, CustomPreferment, PrefermentCulture, PrefermentType, PrefermentTypes};
+pub use crate::ingredient::preferment::pate::PateFermentee;
+pub use crate::ingredient::preferment::poolish::Poolish;
+pub use crate::ingredient::preferment::preferment::{BigaHydration, HydrationPercent, Portion, PortionPercent, Preferment, SolvedPortion, SugarPercent, YeastPercent, YudaneHydration};
+pub use crate::ingredient::preferment::sponge::{Liquid, Sponge};
 pub use crate::ingredient::preferment::starter::Starter;
 pub use crate::ingredient::preferment::tangzhong::Tangzhong;
+pub use crate::ingredient::preferment::yudane::Yudane;
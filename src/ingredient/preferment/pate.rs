@@ -0,0 +1,175 @@
+use std::rc::Rc;
+
+use super::preferment::*;
+use crate::common::mass::Ratio;
+use crate::fermentation::Leavening;
+use crate::ingredient::SaltPercentage;
+use crate::recipe::Hydration;
+use crate::{common::{Field, Gram}, ingredient::{Ingredient, Summary}, Result};
+
+use prettytable::{row, Table};
+
+/// Pâte fermentée (old dough) is a piece of a fully mixed dough, flour, water, salt and yeast, kept from a previous
+/// batch or mixed the day before and left to ferment in the fridge.
+/// It adds strength and depth of flavor like a long fermentation, in a single day bake.
+///
+/// As it is already salted, its salt is taken off the salt added to the final dough
+/// so the recipe still holds the requested salt percentage.
+///
+/// Pâte fermentée is described by
+/// - A Flour content as a percent of the total flour content
+/// - Hydration percent (The old dough's, 50% to 120%)
+/// - Salt as percent of its flour (Usually 2%)
+/// - Yeast as percent of its flour (Usually 0.5% instant yeast)
+///
+#[derive(Debug)]
+pub struct PateFermentee {
+  total_flour: Rc<Gram>,
+  portion: Portion,
+  hydration: HydrationPercent,
+  salt: SaltPercentage,
+  yeast: YeastPercent,
+}
+
+impl PateFermentee {
+  /// Salt as percent of the old dough's flour, when not given
+  pub const SALT: f32 = 2.0;
+
+  /// Yeast as percent of the old dough's flour, when not given
+  pub const YEAST: f32 = 0.5;
+
+  pub fn new(
+    total_flour: &Rc<Gram>,
+    portion: PortionPercent,
+    hydration: HydrationPercent,
+    salt: SaltPercentage,
+    yeast: YeastPercent,
+  ) -> Self {
+    PateFermentee {
+      total_flour: total_flour.clone(),
+      portion: portion.into(),
+      hydration,
+      salt,
+      yeast,
+    }
+  }
+
+  pub fn build(portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description =
+      args.description().specialize("pâte fermentée", "pate:<ratio>:<hydration>[:<salt>[:<yeast>]]", "pate:20:70:2:0.5");
+    let args = args.rebase(&description).split(':', &["hydration", "salt", "yeast"], 1)?;
+    let hydration: Hydration = args[0].percent()?;
+    let salt = match args.get(1) {
+      Some(salt) => salt.percent()?,
+      None => SaltPercentage::checked(Self::SALT)?,
+    };
+    let yeast = match args.get(2) {
+      Some(yeast) => yeast.percent()?,
+      None => YeastPercent::checked(Self::YEAST)?,
+    };
+    let hydration = hydration.rebound()?;
+    Ok(Box::new(PateFermentee { total_flour: total_mass.clone(), portion, hydration, salt, yeast }))
+  }
+
+  fn comment(&self) -> String {
+    format!(
+      "{} Hydration, {} of total flour, {} salt, {} yeast",
+      self.hydration, self.portion, self.salt, self.yeast
+    )
+  }
+
+  fn salt(&self) -> Gram {
+    self.flour() * self.salt
+  }
+
+  fn yeast(&self) -> Gram {
+    self.flour() * self.yeast
+  }
+}
+
+impl Ingredient for PateFermentee {
+  fn water(&self) -> Gram {
+    self.flour() * self.hydration
+  }
+
+  fn flour(&self) -> Gram {
+    *self.total_flour * self.portion
+  }
+
+  /// The salt and yeast
+  fn other(&self) -> Gram {
+    self.salt() + self.yeast()
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let flour_ratio_flour: Ratio = self.flour().as_ratio_of(&self.total_flour);
+    let salt_ratio_flour: Ratio = self.salt().as_ratio_of(&self.total_flour);
+    let yeast_ratio_flour: Ratio = self.yeast().as_ratio_of(&self.total_flour);
+
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
+    let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
+    let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
+    let salt_ratio_total: Ratio = self.salt().as_ratio_of(&total);
+    let yeast_ratio_total: Ratio = self.yeast().as_ratio_of(&total);
+
+    let comment = self.comment();
+    table.add_row(row![lb -> "PATE FERMENTEE", "",   rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
+    table.add_row(row!["",             l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total]);
+    table.add_row(row!["",             l -> "water",  r   -> self.water(),  c -> "", r -> water_ratio_total]);
+    table.add_row(row!["",             l -> "salt",   r   -> self.salt(),   r -> salt_ratio_flour, r -> salt_ratio_total]);
+    table.add_row(row!["",             l -> "yeast",  r   -> self.yeast(),  r -> yeast_ratio_flour, r -> yeast_ratio_total]);
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("pate fermentee", self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(vec![
+        Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+        Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+        Summary::new("salt", Gram::ZERO, Gram::ZERO, self.salt(), total_flour, total),
+        Summary::new("yeast", Gram::ZERO, Gram::ZERO, self.yeast(), total_flour, total),
+      ])
+  }
+
+  fn leavening(&self) -> Option<Leavening> {
+    Some(Leavening::Preferment { flour: self.flour(), yeast: self.yeast() })
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("pate").then(|| self.total())
+  }
+
+  fn carries(&self, name: &str) -> Gram {
+    match name.to_lowercase().as_str() {
+      "salt" => self.salt(),
+      "yeast" => self.yeast(),
+      _ => Gram::ZERO,
+    }
+  }
+}
+
+impl Preferment for PateFermentee {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Error, RecipeBuilder};
+
+  #[test]
+  fn salt_is_taken_off_the_final_dough() {
+    let recipe = RecipeBuilder::new(1000).salt(2.0).preferment_desc("pate:20:70").build().unwrap();
+    assert_eq!(recipe.mass_of("pate"), Some(Gram::from(345)));
+    assert_eq!(recipe.mass_of("salt"), Some(Gram::from(16)));
+    assert_eq!(recipe.water(), 700.into());
+
+    let salt = recipe.ingredients().iter().fold(Gram::ZERO, |a, i| a + i.carries("salt"));
+    assert_eq!(salt + recipe.mass_of("salt").unwrap(), Gram::from(20));
+  }
+
+  #[test]
+  fn more_salt_than_the_recipe_calls_for_is_an_error() {
+    let builder = RecipeBuilder::new(1000).salt(1.0).preferment_desc("pate:30:70:4");
+    assert_eq!(builder.build().unwrap_err(), Error::ExcessCarried("salt".to_owned(), Gram::from(12), Gram::from(10)));
+  }
+}
@@ -0,0 +1,151 @@
+use std::rc::Rc;
+
+use super::preferment::*;
+use crate::common::mass::Ratio;
+use crate::fermentation::Leavening;
+use crate::{common::{Field, Gram}, ingredient::{Ingredient, Summary}, Result};
+
+use prettytable::{row, Table};
+
+/// Poolish is a wet preferment of equal parts flour and water (100% hydration), leavened with a
+/// tiny amount of commercial yeast and fermented for 12-16 hours at room temperature.
+/// It lends the dough extensibility and a mild, nutty flavor, and is traditional in baguettes and ciabatta.
+///
+/// Like a `Starter` a poolish is described by
+/// - A Flour content as a percent of the total flour content
+/// - Hydration percent (Usually 100%)
+/// - Yeast as percent of the poolish's flour (Usually 0.1% instant yeast)
+///
+#[derive(Debug)]
+pub struct Poolish {
+  total_flour: Rc<Gram>,
+  portion: Portion,
+  hydration: HydrationPercent,
+  yeast: YeastPercent,
+}
+
+impl Poolish {
+  /// Yeast as percent of the poolish's flour, when not given
+  pub const YEAST: f32 = 0.1;
+
+  pub fn new(total_flour: &Rc<Gram>, portion: PortionPercent, hydration: HydrationPercent, yeast: YeastPercent) -> Self {
+    Poolish {
+      total_flour: total_flour.clone(),
+      portion: portion.into(),
+      hydration,
+      yeast,
+    }
+  }
+
+  pub fn build(portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description =
+      args.description().specialize("poolish", "poolish:<ratio>:<hydration>[:<yeast>]", "poolish:30:100:0.1");
+    let args = args.rebase(&description).split(':', &["hydration", "yeast"], 1)?;
+    let hydration = &args[0];
+    let yeast = match args.get(1) {
+      Some(yeast) => yeast.percent()?,
+      None => YeastPercent::checked(Self::YEAST)?,
+    };
+    Ok(Box::new(Poolish { total_flour: total_mass.clone(), portion, hydration: hydration.percent()?, yeast }))
+  }
+
+  fn comment(&self) -> String {
+    format!("{} Hydration, {} of total flour, {} yeast", self.hydration, self.portion, self.yeast)
+  }
+}
+
+impl Ingredient for Poolish {
+  fn water(&self) -> Gram {
+    self.flour() * self.hydration
+  }
+
+  fn flour(&self) -> Gram {
+    *self.total_flour * self.portion
+  }
+
+  /// The yeast
+  fn other(&self) -> Gram {
+    self.flour() * self.yeast
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let flour_ratio_flour: Ratio = self.flour().as_ratio_of(&self.total_flour);
+    let yeast_ratio_flour: Ratio = self.other().as_ratio_of(&self.total_flour);
+
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
+    let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
+    let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
+    let yeast_ratio_total: Ratio = self.other().as_ratio_of(&total);
+
+    let comment = self.comment();
+    table.add_row(row![lb -> "POOLISH", "",            rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
+    table.add_row(row!["",             l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total]);
+    table.add_row(row!["",             l -> "water",  r   -> self.water(),  c -> "", r -> water_ratio_total]);
+    table.add_row(row!["",             l -> "yeast",  r   -> self.other(),  r -> yeast_ratio_flour, r -> yeast_ratio_total]);
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("poolish", self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(vec![
+        Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+        Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+        Summary::new("yeast", Gram::ZERO, Gram::ZERO, self.other(), total_flour, total),
+      ])
+  }
+
+  fn leavening(&self) -> Option<Leavening> {
+    Some(Leavening::Preferment { flour: self.flour(), yeast: self.other() })
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("poolish").then(|| self.total())
+  }
+
+  fn carries(&self, name: &str) -> Gram {
+    if name.eq_ignore_ascii_case("yeast") {
+      self.other()
+    } else {
+      Gram::ZERO
+    }
+  }
+}
+
+impl Preferment for Poolish {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ingredient::preferment::BUILDER;
+  use arbtest::arbtest;
+
+  #[test]
+  fn valid_ratio_to_mass() {
+    arbtest(|u| {
+      let total_flour: Rc<Gram> = Rc::new(u.int_in_range(10..=5000)?.into());
+      let portion: PortionPercent = u.int_in_range(PortionPercent::range())?.try_into().unwrap();
+      let hydration: HydrationPercent = u.int_in_range(HydrationPercent::range())?.try_into().unwrap();
+      let yeast: YeastPercent = u.int_in_range(YeastPercent::range())?.try_into().unwrap();
+
+      let poolish = Poolish::new(&total_flour, portion, hydration, yeast);
+      let poolish_flour = *total_flour * portion;
+
+      assert_eq!(poolish.flour(), poolish_flour);
+      assert_eq!(poolish.water(), poolish_flour * hydration);
+      assert_eq!(poolish.other(), poolish_flour * yeast);
+      assert_eq!(poolish.total(), poolish.flour() + poolish.water() + poolish.other());
+
+      Ok(())
+    });
+  }
+
+  #[test]
+  fn yeast_defaults_to_a_tenth_of_a_percent() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let poolish = BUILDER.get("poolish:30:100", &total_flour).unwrap();
+    assert_eq!(poolish.flour(), 300.into());
+    assert_eq!(poolish.water(), 300.into());
+    assert_eq!(poolish.other(), Gram::from(0.3));
+  }
+}
@@ -1,9 +1,72 @@
-use crate::common::Percent;
+use std::{fmt, ops::Mul};
+
+use crate::common::{Gram, Percent};
 use crate::ingredient::ingredient::Ingredient;
 
+use rust_decimal::Decimal;
+
 pub type PortionPercent = Percent<1, 30, 0>;
+
+/// A portion solved for a preferment's mass or a schedule, kept exact to the hundredth of a percent
+pub type SolvedPortion = Percent<1, 30, 2>;
 pub type HydrationPercent = Percent<50, 500, 0>;
 
+/// A biga is stiff, just enough water for the flour to form a shaggy mass
+pub type BigaHydration = Percent<45, 60, 0>;
+
+/// Scalded flour takes about its weight in water, much less than a cooked roux
+pub type YudaneHydration = Percent<80, 150, 0>;
+
+/// Sugar as percent of the preferment's flour
+pub type SugarPercent = Percent<0, 30, 1>;
+
+/// Commercial yeast as percent of the preferment's flour
+pub type YeastPercent = Percent<0, 5, 2>;
+
+/// The preferment's flour as percent of the total flour, as described or solved for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Portion {
+  Described(PortionPercent),
+  Solved(SolvedPortion),
+}
+
+impl Portion {
+  pub fn as_decimal(&self) -> Decimal {
+    match self {
+      Portion::Described(portion) => portion.as_decimal(),
+      Portion::Solved(portion) => portion.as_decimal(),
+    }
+  }
+}
+
+impl From<PortionPercent> for Portion {
+  fn from(portion: PortionPercent) -> Self {
+    Portion::Described(portion)
+  }
+}
+
+impl From<SolvedPortion> for Portion {
+  fn from(portion: SolvedPortion) -> Self {
+    Portion::Solved(portion)
+  }
+}
+
+impl fmt::Display for Portion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Portion::Described(portion) => portion.fmt(f),
+      Portion::Solved(portion) => portion.fmt(f),
+    }
+  }
+}
+
+impl Mul<Portion> for Gram {
+  type Output = Self;
+  fn mul(self, other: Portion) -> Self {
+    self * other.as_decimal()
+  }
+}
+
 /// Preferment is a mixture of dough components that is allowed to ferment before being added to the final bread dough
 /// It will have water and flour components in varying ratios as well as some other ingredients
 /// A preferment main properties are:
@@ -0,0 +1,229 @@
+use std::rc::Rc;
+
+use super::preferment::*;
+use crate::common::mass::Ratio;
+use crate::fermentation::Leavening;
+use crate::ingredient::enrichment::water_content;
+use crate::{common::{Field, Gram}, ingredient::{Ingredient, Summary}, Result};
+
+use prettytable::{row, Table};
+use rust_decimal::Decimal;
+
+/// The liquid of a sponge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Liquid {
+  Water,
+  /// Whole milk, mostly water, the rest is fat, protein and lactose
+  Milk,
+}
+
+impl Liquid {
+  /// The water's part of the liquid's mass, as the catalog knows it
+  pub fn water_fraction(&self) -> Decimal {
+    water_content(self.name()).map_or(Decimal::ONE, |water| water.as_decimal())
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Liquid::Water => "water",
+      Liquid::Milk => "milk",
+    }
+  }
+}
+
+/// A sponge is the preferment of enriched breads (brioche, challah, milk bread), part of the flour mixed with
+/// the liquid, often milk, some sugar and the yeast, and left to rise for 30 minutes to a few hours
+/// before the rest of the dough is mixed in.
+///
+/// The milk's water counts toward the dough's hydration, its solids and the sugar and yeast are `other`.
+/// The milk, sugar and yeast in the sponge are taken off the same named enrichments of the final dough.
+///
+/// A sponge is described by
+/// - A Flour content as a percent of the total flour content
+/// - Hydration percent, the liquid (not only its water) as percent of the sponge's flour
+/// - The liquid, water or milk
+/// - Sugar as percent of the sponge's flour
+/// - Yeast as percent of the sponge's flour (Usually 1% instant yeast)
+///
+#[derive(Debug)]
+pub struct Sponge {
+  total_flour: Rc<Gram>,
+  portion: Portion,
+  hydration: HydrationPercent,
+  liquid: Liquid,
+  sugar: SugarPercent,
+  yeast: YeastPercent,
+}
+
+impl Sponge {
+  /// Yeast as percent of the sponge's flour, when not given
+  pub const YEAST: f32 = 1.0;
+
+  pub fn new(
+    total_flour: &Rc<Gram>,
+    portion: PortionPercent,
+    hydration: HydrationPercent,
+    liquid: Liquid,
+    sugar: SugarPercent,
+    yeast: YeastPercent,
+  ) -> Self {
+    Sponge {
+      total_flour: total_flour.clone(),
+      portion: portion.into(),
+      hydration,
+      liquid,
+      sugar,
+      yeast,
+    }
+  }
+
+  pub fn build(portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description = args.description().specialize(
+      "sponge",
+      "sponge:<ratio>:<hydration>[:<liquid>[:<sugar>[:<yeast>]]]",
+      "sponge:30:80:milk:5:1",
+    );
+    let args = args.rebase(&description).split(':', &["hydration", "liquid", "sugar", "yeast"], 1)?;
+    let liquid = match args.get(1) {
+      Some(liquid) => match liquid.name()?.to_lowercase().as_str() {
+        "water" => Liquid::Water,
+        "milk" => Liquid::Milk,
+        other => return Err(liquid.error(format!("'{}' is neither water nor milk", other))),
+      },
+      None => Liquid::Water,
+    };
+    let sugar = match args.get(2) {
+      Some(sugar) => sugar.percent()?,
+      None => SugarPercent::ZERO,
+    };
+    let yeast = match args.get(3) {
+      Some(yeast) => yeast.percent()?,
+      None => YeastPercent::checked(Self::YEAST)?,
+    };
+    let hydration = args[0].percent()?;
+    Ok(Box::new(Sponge { total_flour: total_mass.clone(), portion, hydration, liquid, sugar, yeast }))
+  }
+
+  fn comment(&self) -> String {
+    format!(
+      "{} Hydration, {} of total flour, {} sugar, {} yeast",
+      self.hydration, self.portion, self.sugar, self.yeast
+    )
+  }
+
+  /// The liquid, including the milk's solids
+  fn liquid(&self) -> Gram {
+    self.flour() * self.hydration
+  }
+
+  fn sugar(&self) -> Gram {
+    self.flour() * self.sugar
+  }
+
+  fn yeast(&self) -> Gram {
+    self.flour() * self.yeast
+  }
+}
+
+impl Ingredient for Sponge {
+  fn water(&self) -> Gram {
+    self.liquid() * self.liquid.water_fraction()
+  }
+
+  fn flour(&self) -> Gram {
+    *self.total_flour * self.portion
+  }
+
+  /// The milk's solids, sugar and yeast
+  fn other(&self) -> Gram {
+    (self.liquid() - self.water()) + self.sugar() + self.yeast()
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let flour_ratio_flour: Ratio = self.flour().as_ratio_of(&self.total_flour);
+    let sugar_ratio_flour: Ratio = self.sugar().as_ratio_of(&self.total_flour);
+    let yeast_ratio_flour: Ratio = self.yeast().as_ratio_of(&self.total_flour);
+
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
+    let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
+    let liquid_ratio_total: Ratio = self.liquid().as_ratio_of(&total);
+    let sugar_ratio_total: Ratio = self.sugar().as_ratio_of(&total);
+    let yeast_ratio_total: Ratio = self.yeast().as_ratio_of(&total);
+
+    let comment = self.comment();
+    let liquid_comment = match self.liquid {
+      Liquid::Water => String::new(),
+      Liquid::Milk => format!("{} water", self.water()),
+    };
+    table.add_row(row![lb -> "SPONGE", "",            rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
+    table.add_row(row!["",             l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total]);
+    table.add_row(row!["",             l -> self.liquid.name(),  r -> self.liquid(),  c -> "", r -> liquid_ratio_total, liquid_comment]);
+    if self.sugar() > Gram::ZERO {
+      table.add_row(row!["",           l -> "sugar",  r   -> self.sugar(),  r -> sugar_ratio_flour, r -> sugar_ratio_total]);
+    }
+    table.add_row(row!["",             l -> "yeast",  r   -> self.yeast(),  r -> yeast_ratio_flour, r -> yeast_ratio_total]);
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    let solids = self.liquid() - self.water();
+    Summary::of("sponge", self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(vec![
+        Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+        Summary::new(self.liquid.name(), Gram::ZERO, self.water(), solids, total_flour, total),
+        Summary::new("sugar", Gram::ZERO, Gram::ZERO, self.sugar(), total_flour, total),
+        Summary::new("yeast", Gram::ZERO, Gram::ZERO, self.yeast(), total_flour, total),
+      ])
+  }
+
+  fn leavening(&self) -> Option<Leavening> {
+    Some(Leavening::Preferment { flour: self.flour(), yeast: self.yeast() })
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("sponge").then(|| self.total())
+  }
+
+  fn carries(&self, name: &str) -> Gram {
+    match name.to_lowercase().as_str() {
+      "milk" if self.liquid == Liquid::Milk => self.liquid(),
+      "sugar" => self.sugar(),
+      "yeast" => self.yeast(),
+      _ => Gram::ZERO,
+    }
+  }
+}
+
+impl Preferment for Sponge {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::RecipeBuilder;
+
+  #[test]
+  fn milk_counts_toward_hydration() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let sponge = Sponge::new(&total_flour, 30.try_into().unwrap(), 80.try_into().unwrap(), Liquid::Milk, SugarPercent::ZERO, 1.try_into().unwrap());
+    assert_eq!(sponge.water(), Gram::from(208.8));
+    assert_eq!(sponge.other(), Gram::from(31.2) + Gram::from(3));
+    assert_eq!(sponge.total(), Gram::from(300 + 240 + 3));
+  }
+
+  #[test]
+  fn enrichments_in_the_sponge_are_taken_off_the_final_dough() {
+    let recipe = RecipeBuilder::new(1000)
+      .hydration(60)
+      .preferment_desc("sponge:30:80:milk:10:1")
+      .enrichment("sugar", 10)
+      .enrichment("milk", 30)
+      .build()
+      .unwrap();
+
+    assert_eq!(recipe.mass_of("sugar"), Some(Gram::from(70)));
+    assert_eq!(recipe.mass_of("milk"), Some(Gram::from(60)));
+    // The milk added to the final dough is 87% water too
+    assert_eq!(recipe.mass_of("water"), Some(Gram::from(600) - Gram::from(208.8) - Gram::from(52.2)));
+  }
+}
@@ -2,7 +2,8 @@ use std::rc::Rc;
 
 use super::preferment::*;
 use crate::common::mass::Ratio;
-use crate::{common::Gram, ingredient::Ingredient, Error, Result};
+use crate::fermentation::Leavening;
+use crate::{common::{Field, Gram}, ingredient::{Ingredient, Summary}, Result};
 
 use prettytable::{row, Table};
 
@@ -17,37 +18,55 @@ use prettytable::{row, Table};
 /// The abstraction is describing a Starter as
 /// - A Flour content as a percent of the total flour content
 /// - Hyndration percent (Usually 100%, meaning 1:1 flour to water. )
+/// - Optionally, a mass to keep as the next bake's culture, built along but never part of the dough
 ///
 #[derive(Debug)]
 pub struct Starter {
   total_flour: Rc<Gram>,
-  portion: PortionPercent,
+  portion: Portion,
   hydration: HydrationPercent,
+  keep: Gram,
 }
 
-impl<'a> Starter {
+impl Starter {
   pub fn new(total_flour: &Rc<Gram>, portion: PortionPercent, hydration: HydrationPercent) -> Self {
     Starter {
       total_flour: total_flour.clone(),
-      portion,
+      portion: portion.into(),
       hydration,
+      keep: Gram::ZERO,
     }
   }
 
-  pub fn build(args: String, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
-    if let [ratio, hydration] = args.split(':').collect::<Vec<&str>>().as_slice() {
-      Ok(Box::new(Starter::new(
-        total_mass,
-        ratio.parse::<i32>().unwrap().into(),
-        hydration.parse::<i32>().unwrap().into(),
-      )))
+  /// Builds `keep` more than the dough uses, kept as the mother culture
+  pub fn keep(mut self, keep: Gram) -> Self {
+    self.keep = keep;
+    self
+  }
+
+  pub fn build(portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description =
+      args.description().specialize("starter", "starter:<ratio>:<hydration>[:<keep>]", "starter:10:100:50g");
+    let args = args.rebase(&description).split(':', &["hydration", "keep"], 1)?;
+    let hydration = args[0].percent()?;
+    let starter = Starter { total_flour: total_mass.clone(), portion, hydration, keep: Gram::ZERO };
+    Ok(Box::new(match args.get(1) {
+      Some(keep) => starter.keep(keep.mass()?),
+      None => starter,
+    }))
+  }
+
+  fn comment(&self) -> String {
+    let comment = format!("{} Hydration, {} of total flour", self.hydration, self.portion);
+    if self.keep > Gram::ZERO {
+      format!("{}, build {}, use {} in dough, keep {}", comment, self.total() + self.keep, self.total(), self.keep)
     } else {
-      Err(Error::InvalidStarterArgs(args))
+      comment
     }
   }
 }
 
-impl<'a> Ingredient for Starter {
+impl Ingredient for Starter {
   fn water(&self) -> Gram {
     self.flour() * self.hydration
   }
@@ -63,10 +82,7 @@ impl<'a> Ingredient for Starter {
     let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
     let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
 
-    let comment = format!(
-      "{} Hydration, {} of total flour",
-      self.hydration, self.portion
-    );
+    let comment = self.comment();
     table.add_row(row![lb -> "STARTER", "",            rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
     table.add_row(row!["",             l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total]);
     table.add_row(
@@ -74,21 +90,47 @@ impl<'a> Ingredient for Starter {
     );
     table
   }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("starter", self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(vec![
+        Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+        Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+      ])
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("starter").then(|| self.total())
+  }
+
+  fn leavening(&self) -> Option<Leavening> {
+    Some(Leavening::Sourdough(self.flour()))
+  }
+
+  fn kept(&self) -> Gram {
+    self.keep
+  }
+
+  fn with_portion(&self, portion: Portion) -> Option<Box<dyn Ingredient>> {
+    Some(Box::new(Starter { total_flour: self.total_flour.clone(), portion, ..*self }))
+  }
 }
 
-impl<'a> Preferment for Starter {}
+impl Preferment for Starter {}
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::ingredient::preferment::BUILDER;
   use arbtest::arbtest;
 
   #[test]
   fn valid_ratio_to_mass() {
     arbtest(|u| {
       let total_flour: Rc<Gram> = Rc::new(u.int_in_range(10..=5000)?.into());
-      let portion: PortionPercent = u.int_in_range(PortionPercent::range())?.into();
-      let hydration: HydrationPercent = u.int_in_range(HydrationPercent::range())?.into();
+      let portion: PortionPercent = u.int_in_range(PortionPercent::range())?.try_into().unwrap();
+      let hydration: HydrationPercent = u.int_in_range(HydrationPercent::range())?.try_into().unwrap();
 
       let starter = Starter::new(&total_flour, portion, hydration);
       let starter_flour = *total_flour * portion;
@@ -101,4 +143,15 @@ mod tests {
       Ok(())
     });
   }
+
+  #[test]
+  fn kept_culture_is_not_in_the_dough() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let starter = BUILDER.get("starter:10:100:50g", &total_flour).unwrap();
+    assert_eq!(starter.flour(), 100.into());
+    assert_eq!(starter.water(), 100.into());
+    assert_eq!(starter.total(), 200.into());
+    assert_eq!(starter.kept(), 50.into());
+    assert!(starter.summary(1000.into(), 1700.into()).comment.ends_with("build 250.00 g, use 200.00 g in dough, keep 50.00 g"));
+  }
 }
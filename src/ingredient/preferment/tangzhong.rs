@@ -2,7 +2,7 @@ use std::rc::Rc;
 
 use super::preferment::*;
 use crate::common::mass::Ratio;
-use crate::{common::Gram, ingredient::Ingredient, Error, Result};
+use crate::{common::{Field, Gram}, ingredient::{Ingredient, Summary}, Result};
 
 use prettytable::{row, Table};
 
@@ -24,33 +24,31 @@ use prettytable::{row, Table};
 #[derive(Debug)]
 pub struct Tangzhong {
   total_flour: Rc<Gram>,
-  portion: PortionPercent,
+  portion: Portion,
   hydration: HydrationPercent,
 }
 
-impl<'a> Tangzhong {
+impl Tangzhong {
   pub fn new(total_flour: &Rc<Gram>, portion: PortionPercent, hydration: HydrationPercent) -> Self {
     Tangzhong {
       total_flour: total_flour.clone(),
-      portion,
+      portion: portion.into(),
       hydration,
     }
   }
 
-  pub fn build(args: String, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
-    if let [ratio, hydration] = args.split(':').collect::<Vec<&str>>().as_slice() {
-      Ok(Box::new(Tangzhong::new(
-        total_mass,
-        ratio.parse::<i32>().unwrap().into(),
-        hydration.parse::<i32>().unwrap().into(),
-      )))
-    } else {
-      Err(Error::InvalidStarterArgs(args))
-    }
+  pub fn build(portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description = args.description().specialize("tangzhong", "tangzhong:<ratio>:<hydration>", "tangzhong:5:500");
+    let args = args.rebase(&description).split(':', &["hydration"], 1)?;
+    Ok(Box::new(Tangzhong { total_flour: total_mass.clone(), portion, hydration: args[0].percent()? }))
+  }
+
+  fn comment(&self) -> String {
+    format!("{} Hydration, {} of total flour", self.hydration, self.portion)
   }
 }
 
-impl<'a> Ingredient for Tangzhong {
+impl Ingredient for Tangzhong {
   fn water(&self) -> Gram {
     self.flour() * self.hydration
   }
@@ -66,10 +64,7 @@ impl<'a> Ingredient for Tangzhong {
     let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
     let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
 
-    let comment = format!(
-      "{} Hydration, {} of total flour",
-      self.hydration, self.portion
-    );
+    let comment = self.comment();
     table.add_row(row![lb -> "TANGZHONG", "",            rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
     table.add_row(row!["",                l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total]);
     table.add_row(
@@ -77,9 +72,22 @@ impl<'a> Ingredient for Tangzhong {
     );
     table
   }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("tangzhong", self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(vec![
+        Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+        Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+      ])
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("tangzhong").then(|| self.total())
+  }
 }
 
-impl<'a> Preferment for Tangzhong {}
+impl Preferment for Tangzhong {}
 
 #[cfg(test)]
 mod tests {
@@ -90,8 +98,8 @@ mod tests {
   fn valid_ratio_to_mass() {
     arbtest(|u| {
       let total_flour: Rc<Gram> = Rc::new(u.int_in_range(10..=5000)?.into());
-      let portion: PortionPercent = u.int_in_range(PortionPercent::range())?.into();
-      let hydration: HydrationPercent = u.int_in_range(HydrationPercent::range())?.into();
+      let portion: PortionPercent = u.int_in_range(PortionPercent::range())?.try_into().unwrap();
+      let hydration: HydrationPercent = u.int_in_range(HydrationPercent::range())?.try_into().unwrap();
 
       let starter = Tangzhong::new(&total_flour, portion, hydration);
       let starter_flour = *total_flour * portion;
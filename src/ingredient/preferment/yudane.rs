@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use super::preferment::*;
+use crate::common::mass::Ratio;
+use crate::{common::{Field, Gram}, ingredient::{Ingredient, Summary}, Result};
+
+use prettytable::{row, Table};
+
+/// Yudane is the Japanese scalded flour, flour mixed with an equal weight of boiling water and left to rest.
+/// Unlike `Tangzhong` it is not cooked, the hot water alone gelatinizes part of the starch, so it takes far
+/// less water (around 100% hydration) and is added to the dough as a stiff paste.
+///
+/// Benefits of using Yudane:
+///   A soft, moist and springy crumb, the gelatinized starch holds on to the water
+///   A longer shelf life
+///   A slightly sweeter taste, as the resting yudane turns starch into sugars
+///
+/// How to use Yudane:
+///  Pour boiling water (at least 95c) over the flour, mix to a smooth paste, then cover and rest it
+///  in the fridge for 4-12 hours before mixing it into the dough
+///
+#[derive(Debug)]
+pub struct Yudane {
+  total_flour: Rc<Gram>,
+  portion: Portion,
+  hydration: YudaneHydration,
+}
+
+impl Yudane {
+  /// Hydration when not given, equal weights of flour and boiling water
+  pub const HYDRATION: f32 = 100.0;
+
+  pub fn new(total_flour: &Rc<Gram>, portion: PortionPercent, hydration: YudaneHydration) -> Self {
+    Yudane {
+      total_flour: total_flour.clone(),
+      portion: portion.into(),
+      hydration,
+    }
+  }
+
+  pub fn build(portion: Portion, args: Field, total_mass: &Rc<Gram>) -> Result<Box<dyn Ingredient>> {
+    let description = args.description().specialize("yudane", "yudane:<ratio>[:<hydration>]", "yudane:20:100");
+    let args = args.rebase(&description).split(':', &["hydration"], 0)?;
+    let hydration = match args.first() {
+      Some(hydration) => hydration.percent()?,
+      None => YudaneHydration::checked(Self::HYDRATION)?,
+    };
+    Ok(Box::new(Yudane { total_flour: total_mass.clone(), portion, hydration }))
+  }
+
+  fn comment(&self) -> String {
+    format!("{} Hydration, {} of total flour", self.hydration, self.portion)
+  }
+
+  /// How the yudane is made
+  fn preparation() -> (&'static str, &'static str) {
+    ("scald with boiling water, at least 95c", "rest covered in the fridge 4-12 hours")
+  }
+}
+
+impl Ingredient for Yudane {
+  fn water(&self) -> Gram {
+    self.flour() * self.hydration
+  }
+
+  fn flour(&self) -> Gram {
+    *self.total_flour * self.portion
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let flour_ratio_flour: Ratio = self.flour().as_ratio_of(&self.total_flour);
+
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
+    let flour_ratio_total: Ratio = self.flour().as_ratio_of(&total);
+    let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
+
+    let comment = self.comment();
+    // In the order the yudane is made
+    let (scald, rest) = Self::preparation();
+    table.add_row(row![lb -> "YUDANE", "",            rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
+    table.add_row(row!["",             l -> "flour",  r   -> self.flour(),  r -> flour_ratio_flour, r -> flour_ratio_total, i -> scald]);
+    table.add_row(row!["",             l -> "water",  r   -> self.water(),  c -> "", r -> water_ratio_total, i -> rest]);
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    let (scald, rest) = Self::preparation();
+    Summary::of("yudane", self, total_flour, total)
+      .with_comment(format!("{}. {}, {}", self.comment(), scald, rest))
+      .with_components(vec![
+        Summary::new("flour", self.flour(), Gram::ZERO, Gram::ZERO, total_flour, total),
+        Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+      ])
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("yudane").then(|| self.total())
+  }
+}
+
+impl Preferment for Yudane {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ingredient::preferment::BUILDER;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn equal_parts_flour_and_water_by_default() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let yudane = BUILDER.get("yudane:20", &total_flour).unwrap();
+    assert_eq!(yudane.flour(), 200.into());
+    assert_eq!(yudane.water(), 200.into());
+
+    let yudane = BUILDER.get("yudane:20:120", &total_flour).unwrap();
+    assert_eq!(yudane.water(), 240.into());
+
+    let error = diagnostics(BUILDER.get("yudane:20:500", &total_flour).unwrap_err());
+    assert_eq!(error[0].message, "500% is out of range, hydration is 80% to 150%");
+  }
+}
@@ -0,0 +1,151 @@
+use super::ingredient::{Ingredient, Summary};
+use crate::common::mass::*;
+use crate::common::{Description, Field};
+use crate::Result;
+use prettytable::{row, Table};
+use std::rc::Rc;
+
+/// Seeds, cracked grains or porridge soaked (or cooked) in water before being mixed into the dough.
+///
+/// The grains hold on to the water they absorb, it adds to the dough's mass
+/// but does not hydrate the flour like free water does.
+///
+/// A soaker is described by
+/// - The grain as percent of the total flour
+/// - The soak water as percent of the grain
+/// - Optionally, the weight the grain gains by cooking as percent of it, the water it holds once cooked
+///   (the rest of the soak water evaporates)
+///
+#[derive(Debug, Clone)]
+pub struct Soaker {
+  pub name: String,
+  total_flour: Rc<Gram>,
+  grain: Ratio,
+  water: Ratio,
+  gain: Option<Ratio>,
+}
+
+impl Soaker {
+  pub fn new(name: impl Into<String>, total_flour: &Rc<Gram>, grain: Ratio, water: Ratio) -> Self {
+    Soaker {
+      name: name.into(),
+      total_flour: total_flour.clone(),
+      grain,
+      water,
+      gain: None,
+    }
+  }
+
+  /// The grain is cooked, gaining `gain` percent of its weight
+  pub fn gain(mut self, gain: Ratio) -> Self {
+    self.gain = Some(gain);
+    self
+  }
+
+  /// The soaker described by `desc`, e.g. `flax:10:100` or `oats:20:300:200`
+  pub fn parse(desc: &str, total_flour: &Rc<Gram>) -> Result<Self> {
+    let description =
+      Description::new("soaker", desc, "<name>:<grain>:<water>[:<gain>]", "oats:20:300:200");
+    let fields = description.whole("soaker").split(':', &["name", "grain", "water", "gain"], 3)?;
+    let percent = |field: &Field| -> Result<Ratio> { field.percent::<0, 1000, 2>()?.rebound() };
+
+    let soaker = Soaker::new(
+      fields[0].name()?,
+      total_flour,
+      fields[1].percent::<0, 100, 2>()?.rebound()?,
+      percent(&fields[2])?,
+    );
+    Ok(match fields.get(3) {
+      Some(gain) => soaker.gain(percent(gain)?),
+      None => soaker,
+    })
+  }
+
+  fn grain(&self) -> Gram {
+    *self.total_flour * self.grain
+  }
+
+  fn soak_water(&self) -> Gram {
+    self.grain() * self.water
+  }
+
+  fn comment(&self) -> String {
+    match self.gain {
+      Some(gain) => format!("cooked in {} water, gains {}", self.soak_water(), gain),
+      None => format!("soaked in {} water", self.water),
+    }
+  }
+}
+
+impl Ingredient for Soaker {
+  /// The water the grain holds
+  fn water(&self) -> Gram {
+    match self.gain {
+      Some(gain) => self.grain() * gain,
+      None => self.soak_water(),
+    }
+  }
+
+  /// The grain
+  fn other(&self) -> Gram {
+    self.grain()
+  }
+
+  fn bound(&self) -> Gram {
+    self.water()
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let grain_ratio_flour: Ratio = self.grain().as_ratio_of(&self.total_flour);
+
+    let total_ratio_total: Ratio = self.total().as_ratio_of(&total);
+    let grain_ratio_total: Ratio = self.grain().as_ratio_of(&total);
+    let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
+
+    let comment = self.comment();
+    table.add_row(row![lb -> self.name.to_uppercase(), "", rb -> self.total(),  cb -> "",  rb -> total_ratio_total, b -> comment]);
+    table.add_row(row!["",             l -> "grain",  r   -> self.grain(),  r -> grain_ratio_flour, r -> grain_ratio_total]);
+    table.add_row(row!["",             l -> "water",  r   -> self.water(),  c -> "", r -> water_ratio_total]);
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of(&self.name, self, total_flour, total)
+      .with_comment(self.comment())
+      .with_components(vec![
+        Summary::new("grain", Gram::ZERO, Gram::ZERO, self.grain(), total_flour, total),
+        Summary::new("water", Gram::ZERO, self.water(), Gram::ZERO, total_flour, total),
+      ])
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    self.name.eq_ignore_ascii_case(name).then(|| self.total())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn soaked_grain_holds_its_water() {
+    let total_flour = Rc::new(Gram::from(1000));
+    let flax = Soaker::parse("flax:10:100", &total_flour).unwrap();
+    assert_eq!(flax.other(), 100.into());
+    assert_eq!(flax.water(), 100.into());
+    assert_eq!(flax.bound(), 100.into());
+
+    let oats = Soaker::parse("oats:20:300:200", &total_flour).unwrap();
+    assert_eq!(oats.other(), 200.into());
+    assert_eq!(oats.water(), 400.into());
+    assert_eq!(oats.total(), 600.into());
+  }
+
+  #[test]
+  fn invalid_soakers_are_errors() {
+    let total_flour = Rc::new(Gram::from(1000));
+    assert_eq!(diagnostics(Soaker::parse("flax:110:100", &total_flour).unwrap_err())[0].field, "grain");
+    assert!(Soaker::parse("flax:10", &total_flour).is_err());
+  }
+}
@@ -1,5 +1,6 @@
-use super::ingredient::Ingredient;
+use super::ingredient::{Ingredient, Summary};
 use crate::common::mass::*;
+use crate::ddt::Tempering;
 use num_traits::Num;
 use prettytable::{row, Table};
 use std::ops::Add;
@@ -9,9 +10,21 @@ use std::ops::Mul;
 #[derive(Debug, Clone)]
 pub struct Water {
   pub mass: Gram,
+
+  /// The water's temperature reaching the desired dough temperature, when given
+  pub tempering: Option<Tempering>,
 }
 
-impl Water {}
+impl Water {
+  pub fn tempered(mut self, tempering: Tempering) -> Self {
+    self.tempering = Some(tempering);
+    self
+  }
+
+  fn comment(&self) -> String {
+    self.tempering.as_ref().map(|t| t.to_string()).unwrap_or_default()
+  }
+}
 
 impl Ingredient for Water {
   fn water(&self) -> Gram {
@@ -20,9 +33,17 @@ impl Ingredient for Water {
 
   fn describe(&self, mut table: Table, total: Gram) -> Table {
     let water_ratio_total: Ratio = self.water().as_ratio_of(&total);
-    table.add_row(row![ lb -> "WATER", "", rb -> self.water(), cb -> "", rb -> water_ratio_total]);
+    table.add_row(row![ lb -> "WATER", "", rb -> self.water(), cb -> "", rb -> water_ratio_total, self.comment()]);
     table
   }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("water", self, total_flour, total).with_comment(self.comment())
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("water").then_some(self.mass)
+  }
 }
 
 impl<T> From<T> for Water
@@ -30,7 +51,7 @@ where
   T: Into<Gram>,
 {
   fn from(value: T) -> Self {
-    Water { mass: value.into() }
+    Water { mass: value.into(), tempering: None }
   }
 }
 
@@ -42,6 +63,7 @@ where
   fn add(self, other: T) -> Self {
     Water {
       mass: self.mass + other.into(),
+      tempering: None,
     }
   }
 }
@@ -54,6 +76,7 @@ where
   fn mul(self, other: T) -> Self {
     Water {
       mass: self.mass * other.into(),
+      tempering: None,
     }
   }
 }
@@ -66,6 +89,7 @@ where
   fn div(self, other: T) -> Self {
     Water {
       mass: self.mass / other,
+      tempering: None,
     }
   }
 }
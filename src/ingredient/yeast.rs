@@ -0,0 +1,231 @@
+use super::ingredient::{Ingredient, Summary};
+use crate::common::mass::*;
+use crate::common::{Description, Field, Percent};
+use crate::fermentation::Leavening;
+use crate::Result;
+use prettytable::{row, Table};
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use std::{fmt, rc::Rc};
+
+/// Commercial yeast as percent of the total flour, in thousandths of a percent as doughs take as little as 0.05%
+pub type YeastRatio = Percent<0, 15, 3>;
+
+/// The kinds of commercial yeast, they differ in how much of them leavens the same dough
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum YeastType {
+  /// Instant (rapid rise) dry yeast, mixed straight into the flour
+  Instant,
+  /// Active dry yeast, usually proofed in water first
+  ActiveDry,
+  /// Fresh (cake, compressed) yeast, about 70% water
+  Fresh,
+  /// Instant yeast for sweet doughs, where sugar would slow ordinary yeast down
+  Osmotolerant,
+}
+
+impl YeastType {
+  pub const ALL: [YeastType; 4] = [YeastType::Instant, YeastType::ActiveDry, YeastType::Fresh, YeastType::Osmotolerant];
+
+  /// The mass of this yeast leavening like a gram of instant yeast
+  ///
+  /// ```text
+  ///         instant : active dry : fresh = 1 : 1.25 : 3
+  /// ```
+  pub fn per_instant(&self) -> Decimal {
+    match self {
+      YeastType::Instant | YeastType::Osmotolerant => Decimal::ONE,
+      YeastType::ActiveDry => dec!(1.25),
+      YeastType::Fresh => dec!(3),
+    }
+  }
+
+  /// The mass of `to` yeast leavening like `mass` of this yeast
+  pub fn convert(&self, mass: Gram, to: YeastType) -> Gram {
+    Gram(mass.0 * to.per_instant() / self.per_instant())
+  }
+
+  fn parse(field: &Field) -> Result<Self> {
+    let name = field.name()?.to_lowercase().replace(['-', '_', ' '], "");
+    YeastType::ALL
+      .into_iter()
+      .find(|kind| kind.to_string().replace(' ', "") == name)
+      .ok_or_else(|| field.error(format!("'{}' is not a yeast, expected instant, activedry, fresh or osmotolerant", field.text())))
+  }
+}
+
+impl fmt::Display for YeastType {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      YeastType::Instant => "instant",
+      YeastType::ActiveDry => "active dry",
+      YeastType::Fresh => "fresh",
+      YeastType::Osmotolerant => "osmotolerant",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// Commercial yeast added to the final dough.
+///
+/// It is described by the yeast the formula calls for and its percent of the total flour, and may be
+/// used as another kind of yeast, e.g. a formula calling for 0.4% instant yeast made with fresh yeast
+/// takes 1.2% fresh yeast.
+///
+/// The yeast already in preferments (taken to be instant yeast) is not added again.
+#[derive(Debug, Clone)]
+pub struct Yeast {
+  total_flour: Rc<Gram>,
+  ratio: YeastRatio,
+
+  /// The yeast the ratio is of
+  kind: YeastType,
+
+  /// The yeast used in the dough
+  used: YeastType,
+
+  /// The instant yeast already in preferments
+  carried: Gram,
+}
+
+impl Yeast {
+  pub fn new(total_flour: &Rc<Gram>, kind: YeastType, ratio: YeastRatio) -> Self {
+    Yeast {
+      total_flour: total_flour.clone(),
+      ratio,
+      kind,
+      used: kind,
+      carried: Gram::ZERO,
+    }
+  }
+
+  /// The yeast the ratio is of
+  pub fn kind(&self) -> YeastType {
+    self.kind
+  }
+
+  pub fn with_ratio(mut self, ratio: YeastRatio) -> Self {
+    self.ratio = ratio;
+    self
+  }
+
+  /// Uses the equivalent amount of `used` yeast instead
+  pub fn used_as(mut self, used: YeastType) -> Self {
+    self.used = used;
+    self
+  }
+
+  /// The instant yeast already in preferments, not to be added again
+  pub fn carried(mut self, carried: Gram) -> Self {
+    self.carried = carried;
+    self
+  }
+
+  /// The yeast described by `desc`, `<type>:<ratio>[@<used type>]` e.g. `instant:0.4` or `instant:0.4@fresh`
+  pub fn parse(desc: &str, total_flour: &Rc<Gram>) -> Result<Self> {
+    let description = Description::new("yeast", desc, "<type>:<ratio>[@<used type>]", "instant:0.4@fresh");
+    let whole = description.whole("yeast");
+    let (yeast, used) = match whole.split_once('@', "yeast", "used") {
+      Ok((yeast, used)) => (yeast, Some(YeastType::parse(&used)?)),
+      Err(_) => (whole, None),
+    };
+    let (kind, ratio) = yeast.split_once(':', "type", "ratio")?;
+    let yeast = Yeast::new(total_flour, YeastType::parse(&kind)?, ratio.percent()?);
+    Ok(match used {
+      Some(used) => yeast.used_as(used),
+      None => yeast,
+    })
+  }
+
+  /// The yeast the formula calls for, in its own kind
+  fn called_for(&self) -> Gram {
+    *self.total_flour * self.ratio
+  }
+
+  /// The yeast to add, of the kind used
+  fn mass(&self) -> Gram {
+    let mass = self.kind.convert(self.called_for(), self.used);
+    let carried = YeastType::Instant.convert(self.carried, self.used);
+    Gram((mass - carried).max(Gram::ZERO).0.round_dp(2))
+  }
+
+  fn comment(&self) -> String {
+    let mut comment = vec![format!("{}", self.used)];
+    if self.used != self.kind {
+      comment.push(format!("for {} {}", self.ratio, self.kind));
+    }
+    if self.carried > Gram::ZERO {
+      comment.push(format!("{} in preferments", YeastType::Instant.convert(self.carried, self.used)));
+    }
+    let equivalents: Vec<String> = YeastType::ALL
+      .into_iter()
+      .filter(|kind| kind.per_instant() != self.used.per_instant())
+      .map(|kind| format!("{} {}", Gram(self.used.convert(self.mass(), kind).0.round_dp(2)), kind))
+      .collect();
+    comment.push(format!("or {}", equivalents.join(", ")));
+    comment.join(", ")
+  }
+}
+
+impl Ingredient for Yeast {
+  fn other(&self) -> Gram {
+    self.mass()
+  }
+
+  fn describe(&self, mut table: Table, total: Gram) -> Table {
+    let mass_ratio_flour: Ratio = self.mass().as_ratio_of(&self.total_flour);
+    let mass_ratio_total: Ratio = self.mass().as_ratio_of(&total);
+    table.add_row(
+      row![ b -> "YEAST", "", br -> self.mass(), br -> mass_ratio_flour, rb -> mass_ratio_total, self.comment()],
+    );
+    table
+  }
+
+  fn summary(&self, total_flour: Gram, total: Gram) -> Summary {
+    Summary::of("yeast", self, total_flour, total).with_comment(self.comment())
+  }
+
+  fn mass_of(&self, name: &str) -> Option<Gram> {
+    name.eq_ignore_ascii_case("yeast").then(|| self.mass())
+  }
+
+  fn leavening(&self) -> Option<Leavening> {
+    Some(Leavening::Yeast(self.used.convert(self.mass(), YeastType::Instant)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn types_convert_by_their_strength() {
+    let instant = Gram::from(1);
+    assert_eq!(YeastType::Instant.convert(instant, YeastType::Fresh), 3.into());
+    assert_eq!(YeastType::Instant.convert(instant, YeastType::ActiveDry), Gram::from(1.25));
+    assert_eq!(YeastType::Fresh.convert(3.into(), YeastType::Osmotolerant), instant);
+  }
+
+  #[test]
+  fn sub_gram_amounts_are_kept() {
+    let total_flour = Rc::new(Gram::from(500));
+    let yeast = Yeast::parse("instant:0.07", &total_flour).unwrap();
+    assert_eq!(yeast.other(), Gram::from(0.35));
+
+    let fresh = Yeast::parse("instant:0.4@fresh", &total_flour).unwrap();
+    assert_eq!(fresh.other(), 6.into());
+
+    let carried = Yeast::parse("Active-Dry:0.5", &total_flour).unwrap().carried(1.into());
+    assert_eq!(carried.other(), Gram::from(1.25));
+  }
+
+  #[test]
+  fn invalid_yeasts_are_errors() {
+    let total_flour = Rc::new(Gram::from(500));
+    assert_eq!(diagnostics(Yeast::parse("wild:1", &total_flour).unwrap_err())[0].span, 0..4);
+    assert!(Yeast::parse("instant:20", &total_flour).is_err());
+  }
+}
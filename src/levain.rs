@@ -0,0 +1,228 @@
+use crate::common::mass::*;
+use crate::common::percent::PERCENT;
+use crate::common::{Description, Field, Percent};
+use crate::Result;
+use prettytable::{format, row, Table};
+use rust_decimal::prelude::*;
+use serde::Serialize;
+
+/// Extra levain built on top of what the recipe needs, as percent of it.
+/// Some of it sticks to the jar, the rest may be kept as the next seed
+pub type Overage = Percent<0, 50, 0>;
+
+/// A levain build stage, parts by weight of the culture carried in, the flour and the water fed to it.
+/// i.e. `1:2:2` feeds 1 part culture with 2 parts flour and 2 parts water
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevainStage {
+  pub seed: Decimal,
+  pub flour: Decimal,
+  pub water: Decimal,
+
+  /// The flour fed at this stage, e.g. rye, when it matters
+  pub flour_name: Option<String>,
+}
+
+/// The masses of a single levain build stage
+#[derive(Debug, Clone, Serialize)]
+pub struct StagePlan {
+  /// The stage's `seed:flour:water` parts
+  pub ratio: String,
+
+  /// The culture carried in, the seed or the whole previous stage
+  pub seed: Gram,
+
+  pub flour: Gram,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub flour_name: Option<String>,
+
+  pub water: Gram,
+
+  pub total: Gram,
+}
+
+/// How to build the recipe's levain in stages, starting from a small seed culture
+#[derive(Debug, Clone, Serialize)]
+pub struct LevainPlan {
+  /// The seed culture the first stage starts from
+  pub seed: Gram,
+
+  pub stages: Vec<StagePlan>,
+
+  /// The levain the recipe needs, including the culture kept from it
+  pub required: Gram,
+
+  /// The levain built, including the overage
+  pub levain: Gram,
+
+  /// The hydration of the built levain, the seed is taken to be at `SEED_HYDRATION`
+  pub hydration: Ratio,
+
+  /// The hydration of the recipe's levain
+  pub expected_hydration: Ratio,
+}
+
+impl LevainStage {
+  pub fn new(seed: f32, flour: f32, water: f32) -> Self {
+    let decimal = |v: f32| Decimal::from_f32(v).unwrap_or(Decimal::ZERO);
+    LevainStage {
+      seed: decimal(seed),
+      flour: decimal(flour),
+      water: decimal(water),
+      flour_name: None,
+    }
+  }
+
+  pub fn with_flour(mut self, name: impl Into<String>) -> Self {
+    self.flour_name = Some(name.into());
+    self
+  }
+
+  /// Parses a `<seed>:<flour>:<water>[@<flour name>]` description, e.g. `1:5:5@rye`
+  pub fn parse(desc: &str) -> Result<Self> {
+    let description = Description::new("levain stage", desc, "<seed>:<flour>:<water>[@<flour name>]", "1:5:5@rye");
+    let whole = description.whole("stage");
+    let (parts, flour_name) = match whole.split_once('@', "parts", "flour name") {
+      Ok((parts, name)) => (parts, Some(name.name()?)),
+      Err(_) => (whole, None),
+    };
+
+    let parts = parts.split(':', &["seed", "flour", "water"], 3)?;
+    let part = |field: &Field, positive: bool| {
+      let value = field.number::<Decimal>()?;
+      if value > Decimal::ZERO || (!positive && value.is_zero()) {
+        Ok(value)
+      } else {
+        Err(field.error(format!("{} parts can't be fed", value)))
+      }
+    };
+    let stage = LevainStage {
+      seed: part(&parts[0], true)?,
+      flour: part(&parts[1], false)?,
+      water: part(&parts[2], false)?,
+      flour_name: None,
+    };
+    Ok(match flour_name {
+      Some(name) => stage.with_flour(name),
+      None => stage,
+    })
+  }
+
+  fn parts(&self) -> Decimal {
+    self.seed + self.flour + self.water
+  }
+
+  fn ratio(&self) -> String {
+    format!("{}:{}:{}", self.seed.normalize(), self.flour.normalize(), self.water.normalize())
+  }
+}
+
+impl LevainPlan {
+  /// The seed culture is taken to be a 100% hydration starter
+  pub const SEED_HYDRATION: i32 = 100;
+
+  /// Works backwards from the levain the recipe needs (`required` at `expected_hydration`, plus `overage`),
+  /// each stage's seed is the previous stage's whole output
+  ///
+  /// ```text
+  ///         <seed> = <output> x seed / (seed + flour + water)
+  /// ```
+  pub fn new(stages: &[LevainStage], required: Gram, expected_hydration: Ratio, overage: Overage) -> Self {
+    let levain = required + required * overage;
+
+    let mut output = levain;
+    let mut plans = vec![];
+    for stage in stages.iter().rev() {
+      let part = |parts: Decimal| Gram((output.0 * parts / stage.parts()).round_dp(2));
+      let (seed, flour, water) = (part(stage.seed), part(stage.flour), part(stage.water));
+      plans.push(StagePlan {
+        ratio: stage.ratio(),
+        seed,
+        flour,
+        flour_name: stage.flour_name.clone(),
+        water,
+        total: seed + flour + water,
+      });
+      output = seed;
+    }
+    plans.reverse();
+
+    let seed_flour = output / (Decimal::ONE + Decimal::from(Self::SEED_HYDRATION) / PERCENT);
+    let flour = plans.iter().fold(seed_flour, |a, p| a + p.flour);
+    let water = plans.iter().fold(output - seed_flour, |a, p| a + p.water);
+
+    LevainPlan {
+      seed: output,
+      stages: plans,
+      required,
+      levain,
+      hydration: water.as_ratio_of(&flour),
+      expected_hydration,
+    }
+  }
+
+  pub fn display(&self) {
+    println!("LEVAIN BUILD");
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.add_row(row![ cbFy => "#", "stage", "seed", "flour", "water", "total"]);
+    table.add_row(row![b -> "SEED", "", r -> self.seed, "", "", r -> self.seed]);
+    for (n, stage) in self.stages.iter().enumerate() {
+      let flour = match &stage.flour_name {
+        Some(name) => format!("{} {}", stage.flour, name),
+        None => stage.flour.to_string(),
+      };
+      table.add_row(row![b -> n + 1, stage.ratio, r -> stage.seed, r -> flour, r -> stage.water, r -> stage.total]);
+    }
+    table.printstd();
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    table.add_row(row!["", "REQUIRED", r -> self.required]);
+    table.add_row(row!["", "BUILT", r -> self.levain]);
+    if self.hydration == self.expected_hydration {
+      table.add_row(row!["", "HYDRATION", r -> self.hydration]);
+    } else {
+      table.add_row(row!["", Fr -> "HYDRATION", rFr -> self.hydration, Fr -> "EXPECTED HYDRATION:", Frr -> self.expected_hydration]);
+    }
+    table.printstd();
+    println!("* The seed is taken to be a {}% hydration starter", Self::SEED_HYDRATION);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn works_backwards_to_the_seed() {
+    let stages = [LevainStage::new(1.0, 2.0, 2.0), LevainStage::new(1.0, 5.0, 5.0).with_flour("rye")];
+    let plan = LevainPlan::new(&stages, 220.into(), 100.try_into().unwrap(), Overage::ZERO);
+
+    assert_eq!(plan.stages[1].seed, 20.into());
+    assert_eq!(plan.stages[1].flour, 100.into());
+    assert_eq!(plan.stages[1].total, 220.into());
+    assert_eq!(plan.stages[0].total, 20.into());
+    assert_eq!(plan.seed, 4.into());
+    assert_eq!(plan.hydration, 100.try_into().unwrap());
+  }
+
+  #[test]
+  fn overage_is_built_on_top() {
+    let stages = [LevainStage::new(1.0, 5.0, 5.0)];
+    let plan = LevainPlan::new(&stages, 200.into(), 100.try_into().unwrap(), 10.try_into().unwrap());
+    assert_eq!(plan.levain, 220.into());
+    assert_eq!(plan.stages[0].seed, 20.into());
+  }
+
+  #[test]
+  fn stages_are_parsed() {
+    assert_eq!(LevainStage::parse("1:5:5@rye").unwrap(), LevainStage::new(1.0, 5.0, 5.0).with_flour("rye"));
+    assert_eq!(diagnostics(LevainStage::parse("0:5:5").unwrap_err())[0].field, "seed");
+
+    let error = diagnostics(LevainStage::parse("1:inf:5").unwrap_err()).remove(0);
+    assert_eq!((error.field.as_str(), error.span), ("flour", 2..5));
+    assert_eq!(diagnostics(LevainStage::parse("1:5:NaN").unwrap_err())[0].field, "water");
+  }
+}
@@ -1,23 +1,77 @@
-mod common;
-mod error;
-mod ingredient;
+//! # pn
+//!
+//! Bread recipes from baker's percentages.
+//!
+//! A recipe is described relative to its total flour mass, every ingredient as percent of it.
+//! [`RecipeBuilder`] assembles a [`recipe::Recipe`] from those percentages, the ingredients it is made of are
+//! in [`ingredient`], built on the [`common::Gram`] and [`common::Percent`] units.
+//!
+//! ```
+//! use pn::ingredient::preferment::Starter;
+//! use pn::RecipeBuilder;
+//!
+//! # fn main() -> pn::Result<()> {
+//! let (portion, hydration) = (10.try_into()?, 100.try_into()?);
+//! let recipe = RecipeBuilder::new(1000)
+//!   .hydration(70)
+//!   .flour("white", 80)
+//!   .flour("rye", 20)
+//!   .preferment(move |flour| Starter::new(flour, portion, hydration))
+//!   .build()?;
+//!
+//! assert_eq!(recipe.total(), 1720.into());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `pn` command line is a thin wrapper that turns its arguments into a [`document::RecipeDocument`]
+//! and the document into a [`RecipeBuilder`].
+
+pub mod builder;
+pub mod catalog;
+pub mod common;
+pub mod ddt;
+pub mod error;
+pub mod fermentation;
+pub mod ingredient;
+pub mod levain;
 
 mod macros;
+pub mod document;
 pub mod recipe;
+pub mod weighed;
 
-use crate::error::{Error, Result};
-use clap::{arg, command, Parser};
+pub use crate::builder::RecipeBuilder;
+use crate::catalog::{Entries, CATALOG};
+use crate::document::RecipeDocument;
+pub use crate::error::{Error, Result};
+use crate::error::collect;
+use crate::weighed::Weighed;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use clap::builder::styling::{AnsiColor as Ansi, Styles};
+use std::path::PathBuf;
 
- const MY_STYLES: Styles = Styles::styled()
+const MY_STYLES: Styles = Styles::styled()
   .header(Ansi::Red.on_default().bold())
   .usage(Ansi::Red.on_default().bold())
   .literal(Ansi::Blue.on_default().bold())
   .placeholder(Ansi::Green.on_default());
 
+/// How the computed recipe is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+  /// Human readable tables
+  Table,
+  /// Structured JSON, for other tools to consume
+  Json,
+}
+
 #[derive(Parser)]
-#[command(author, version, about, long_about = None, styles=MY_STYLES)]
+#[command(author, version, about, long_about = None, styles=MY_STYLES, args_conflicts_with_subcommands = true)]
 pub struct Cli {
+  #[command(subcommand)]
+  command: Option<Command>,
+
   #[arg(
     short,
     long,
@@ -27,6 +81,17 @@ pub struct Cli {
   )]
   mass: Option<i32>,
 
+  #[arg(
+    long,
+    value_name = "MASS",
+    conflicts_with = "mass",
+    help = "Dough weight of a single piece in gram, the total flour mass is solved to yield it"
+  )]
+  dough: Option<f32>,
+
+  #[arg(long, value_name = "COUNT", help = "Number of pieces the dough is divided to. Default: 1")]
+  pieces: Option<u32>,
+
   #[arg(short = 'd', long, value_name = "HYDRATION", default_value = "70")]
   hydration: Option<i32>,
 
@@ -54,66 +119,276 @@ pub struct Cli {
     short = 'e',
     long,
     action = clap::ArgAction::Append,
-    help = "Any addition to the dough that is not the basics water/flour/salt. Format <name>:<mass> or <name>%<percent of flour>"
+    help = "Any addition to the dough that is not the basics water/flour/salt. Format <name>:<mass>[:<water %>] or <name>%<percent of flour>[:<water %>]. Milk, eggs, butter, etc. count their water toward the hydration"
   )]
   enrichment: Vec<String>,
 
+  #[arg(
+    long,
+    value_name = "SOAKER",
+    action = clap::ArgAction::Append,
+    help = "Grains soaked or cooked in water <name>:<grain % of flour>:<water % of grain>[:<cooked weight gain %>]. Example: flax:10:100 or oats:20:300:200"
+  )]
+  soaker: Vec<String>,
+
+  #[arg(
+    long,
+    value_name = "YEAST",
+    help = "Commercial yeast <type>:<% of flour>[@<type used>], types are instant, activedry, fresh and osmotolerant. Example: instant:0.4 or instant:0.4@fresh to use fresh yeast for a formula calling for instant yeast"
+  )]
+  yeast: Option<String>,
+
+  // -- Recipe files
+  #[arg(
+    long,
+    value_name = "FILE",
+    conflicts_with_all = ["mass", "hydration", "flour", "salt_percentage", "preferment", "enrichment", "soaker", "yeast"],
+    help = "Load the recipe from a TOML file instead of the ingredient flags"
+  )]
+  recipe: Option<PathBuf>,
+
+  #[arg(long, value_name = "FILE", help = "Load preferment types, in addition to the built in ones, from a TOML file")]
+  preferment_types: Option<PathBuf>,
+
+  #[arg(long, value_name = "FILE", help = "Save the recipe as a TOML file")]
+  save: Option<PathBuf>,
+
+  #[arg(long, help = "Recipe title, stored with the recipe")]
+  title: Option<String>,
+
+  #[arg(long, help = "Free text notes, stored with the recipe")]
+  notes: Option<String>,
+
+  #[arg(long, global = true, value_enum, default_value_t = Format::Table, help = "Output format")]
+  format: Format,
+
+  #[arg(long, global = true, value_name = "FILE", help = "Add ingredients to the built in catalog from a TOML file")]
+  catalog: Option<PathBuf>,
+
   // -- Transformations
-  // #[arg(long)]
-  // reset_starter_weight: Option<f32>,
+  #[arg(
+    long,
+    value_name = "INGREDIENT:MASS",
+    conflicts_with_all = ["mass", "dough"],
+    help = "Scale the recipe around an ingredient's mass, keeping all percentages. Example: starter:180, rye:1000 or water:350"
+  )]
+  anchor: Option<String>,
 
-  // #[arg(long)]
-  // reset_water_weight: Option<f32>,
+  // -- Levain build
+  #[arg(
+    long,
+    value_name = "STAGE",
+    action = clap::ArgAction::Append,
+    help = "A stage building the starter from a seed culture <seed>:<flour>:<water>[@<flour>], in order. Example: --levain 1:2:2 --levain 1:5:5@rye"
+  )]
+  levain: Vec<String>,
+
+  #[arg(long, value_name = "PERCENT", help = "Extra levain to build, as percent of what the recipe needs. Default: 0")]
+  levain_overage: Option<f32>,
+
+  // -- Fermentation
+  #[arg(
+    long,
+    value_name = "CELSIUS",
+    allow_negative_numbers = true,
+    help = "Dough temperature in °C, 15 to 32, the bulk, final proof and preferment peak times are estimated at. Default: 24"
+  )]
+  temperature: Option<f32>,
+
+  #[arg(
+    long,
+    value_name = "START-END",
+    help = "Times of the day the bulk fermentation starts and ends, the starter's portion (or the yeast when there is no starter) is solved to fit them at the dough temperature. Example: 09:00-18:00"
+  )]
+  schedule: Option<String>,
+
+  #[arg(
+    long,
+    value_name = "TEMPERATURES",
+    allow_hyphen_values = true,
+    help = "Desired dough temperature, the water added is tempered to reach it <target>:<flour>:<room>:<mixer friction>[:<preferment>] in °C. Example: 24:20:22:2:26"
+  )]
+  ddt: Option<String>,
+
+  #[arg(
+    long,
+    value_name = "CELSIUS",
+    requires = "ddt",
+    help = "The coldest water the tap gives, colder water is cooled with ice. Default: 10"
+  )]
+  tap_temperature: Option<f32>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+  /// Derive the baker's percentages of a recipe written in grams
+  Weigh(WeighArgs),
+
+  /// List the ingredients pn knows, their composition and allergens
+  Catalog,
+}
+
+#[derive(Args)]
+pub struct WeighArgs {
+  #[arg(short, long, action = clap::ArgAction::Append, required = true, help = "Flour added to the dough <name>:<mass>. Example: white:500")]
+  flour: Vec<String>,
+
+  #[arg(short, long, default_value = "0", help = "Water added to the dough in gram")]
+  water: f32,
+
+  #[arg(
+    short,
+    long,
+    action = clap::ArgAction::Append,
+    help = "Preferment <name>:<mass>:<hydration>. Example: starter:100:100 for 100g of 100% hydrated starter"
+  )]
+  preferment: Vec<String>,
+
+  #[arg(short, long, default_value = "0", help = "Salt in gram")]
+  salt: f32,
+
+  #[arg(short, long, action = clap::ArgAction::Append, help = "Any other addition to the dough <name>:<mass>")]
+  enrichment: Vec<String>,
 }
 
-// #[derive(Debug)]
-// pub struct Recipe {
-//   /// Referece to total flour mass
-//   total_mass: Rc<Gram>,
-
-//   /// A list to all ingredients in recipe
-//   ingredients: Vec<Box<dyn Ingredient>>,
-
-//   /// Required recipe hydration ( Liquid / Total flour mass)
-//   hydration: Hydration,
-// }
-
-// impl Recipe {
-//   fn new(total_mass: Gram, hydration: Hydration) -> Self {
-//     Recipe {
-//       total_mass: Rc::new(total_mass),
-//       ingredients: vec![],
-//       hydration: hydration,
-//     }
-//   }
-
-//   /// Returns the total water in recipe
-//   fn water(&self) -> Gram {
-//     self.ingredients.iter().fold(Gram::ZERO, |a, i|
-//       a + i.water()
-//     )
-//   }
-
-//   /// The amount of additional water required to achieve the desired hydration
-//   ///
-//   ///         <Missing water> =  <Total flour mass> x Hydration - <current water content>
-//   fn missing_water(&self) -> Gram {
-//     (*self.total_mass * self.hydration) - self.water()
-//   }
-
-//   /// If the requested hydration is not reached
-//   /// Add appropriate water
-//   /// If the hydration is already exceeded do nothing
-//   fn add_missing_water(&mut self) -> &mut Self{
-//     let to_add = self.missing_water();
-//     if to_add > Gram::ZERO {
-//       self.ingredients.push(Box::new( Water { mass: to_add }));
-//     }
-//     self
-//   }
-
-// }
+impl WeighArgs {
+  pub fn weighed(&self) -> Result<Weighed> {
+    let weighed = Weighed {
+      water: self.water.into(),
+      salt: self.salt.into(),
+      ..Default::default()
+    };
+    let mut errors = vec![];
+    let mut weighed = weighed;
+    for flour in &self.flour {
+      weighed = collect(&mut errors, weighed.clone().add_flour(flour)).unwrap_or(weighed);
+    }
+    for preferment in &self.preferment {
+      weighed = collect(&mut errors, weighed.clone().add_preferment(preferment)).unwrap_or(weighed);
+    }
+    for enrichment in &self.enrichment {
+      weighed = collect(&mut errors, weighed.clone().add_enrichment(enrichment)).unwrap_or(weighed);
+    }
+    Error::merge(errors)?;
+    Ok(weighed)
+  }
+}
+
+impl Cli {
+  /// The recipe described by the command line, either loaded from `--recipe` or made of the ingredient flags.
+  /// The transformations (`--dough`, `--pieces`, `--anchor`), the levain build, `--title` and `--notes` override the loaded ones.
+  pub fn document(&self) -> Result<RecipeDocument> {
+    let mut document = match &self.recipe {
+      Some(path) => RecipeDocument::load(path)?,
+      None => RecipeDocument {
+        title: None,
+        notes: None,
+        mass: self.mass.unwrap(),
+        dough: None,
+        pieces: None,
+        anchor: None,
+        hydration: self.hydration.unwrap(),
+        salt: self.salt_percentage.unwrap(),
+        flours: self.flour.clone(),
+        preferments: self.preferment.clone(),
+        enrichments: self.enrichment.clone(),
+        soakers: self.soaker.clone(),
+        yeast: self.yeast.clone(),
+        levain: vec![],
+        levain_overage: None,
+        temperature: None,
+        schedule: None,
+        ddt: None,
+        tap_temperature: None,
+        preferment_types: None,
+        catalog: None,
+      },
+    };
+
+    if self.dough.is_some() {
+      document.dough = self.dough;
+      document.anchor = None;
+    }
+    if self.anchor.is_some() {
+      document.anchor = self.anchor.clone();
+      document.dough = None;
+    }
+    if self.pieces.is_some() {
+      document.pieces = self.pieces;
+    }
+    if !self.levain.is_empty() {
+      document.levain = self.levain.clone();
+    }
+    if self.levain_overage.is_some() {
+      document.levain_overage = self.levain_overage;
+    }
+    if self.temperature.is_some() {
+      document.temperature = self.temperature;
+    }
+    if self.schedule.is_some() {
+      document.schedule = self.schedule.clone();
+    }
+    if self.ddt.is_some() {
+      document.ddt = self.ddt.clone();
+    }
+    if self.tap_temperature.is_some() {
+      document.tap_temperature = self.tap_temperature;
+    }
+    if self.title.is_some() {
+      document.title = self.title.clone();
+    }
+    if self.notes.is_some() {
+      document.notes = self.notes.clone();
+    }
+    // Kept absolute, the saved recipe loads them from wherever it is built
+    let absolute = |path: &PathBuf| {
+      std::path::absolute(path).map_err(|e| Error::ConfigFileAccess(path.display().to_string(), e.to_string()))
+    };
+    if let Some(path) = &self.preferment_types {
+      document.preferment_types = Some(absolute(path)?);
+    }
+    if let Some(path) = &self.catalog {
+      document.catalog = Some(absolute(path)?);
+    }
+    Ok(document)
+  }
+}
 
 pub fn get_args() -> Result<Cli> {
   Ok(Cli::parse())
 }
+
+/// Builds and displays the recipe described by `cli`, saving it first when asked to
+pub fn run(cli: Cli) -> Result<()> {
+  // A recipe registers its catalog along with its preferment types, see `RecipeDocument::register`
+  if let (Some(path), Some(_)) = (&cli.catalog, &cli.command) {
+    CATALOG.register(Entries::load(path)?);
+  }
+  match &cli.command {
+    Some(Command::Weigh(args)) => {
+      let formula = args.weighed()?.formula()?;
+      return match cli.format {
+        Format::Table => formula.display(),
+        Format::Json => formula.display_json(),
+      };
+    }
+    Some(Command::Catalog) => {
+      return match cli.format {
+        Format::Table => CATALOG.display(),
+        Format::Json => CATALOG.display_json(),
+      };
+    }
+    None => {}
+  }
+
+  let document = cli.document()?;
+  document.register()?;
+  let recipe = document.builder()?.build()?;
+  if let Some(path) = &cli.save {
+    document.save(path)?;
+  }
+  match cli.format {
+    Format::Table => recipe.display(),
+    Format::Json => recipe.display_json(),
+  }
+}
@@ -1,10 +1,8 @@
-// use pn::recipe::Recipe;
-
-use pn::recipe::Recipe;
+use colored::Colorize;
 
 fn main() {
-  pn::get_args()
-    .and_then(Recipe::build)
-    .and_then(Recipe::display)
-    .unwrap();
+  if let Err(e) = pn::get_args().and_then(pn::run) {
+    eprintln!("{}: {}", "error".red().bold(), e);
+    std::process::exit(1);
+  }
 }
@@ -1,28 +1,90 @@
+use crate::catalog::CATALOG;
 use crate::common::mass::*;
 use crate::common::percent::*;
-use crate::ingredient::flour::Flours;
-use crate::ingredient::preferment;
-use crate::ingredient::Enrichment;
-use crate::ingredient::SaltPercentage;
-use crate::ingredient::{Ingredient, Water};
-use crate::Cli;
+use crate::ddt::{Ddt, Tempering};
+use crate::ingredient::{Ingredient, Summary, Water};
+use crate::document::RecipeDocument;
+use crate::fermentation::{Fermentation, Leavening};
+use crate::levain::LevainPlan;
 use crate::{Error, Result};
 use prettytable::{format, row, Table};
+use rust_decimal::Decimal;
+use serde::Serialize;
 use std::fmt::Debug;
 use std::rc::Rc;
 
 pub type Hydration = Percent<50, 120, 0>;
 
+/// The hydration a recipe ends up with, the water of enrichments like milk may exceed the `Hydration` asked for
+pub type ResultingHydration = Percent<0, 100_000_000, 0>;
+
 #[derive(Debug)]
 pub struct Recipe {
   /// Referece to total flour mass
-  total_mass: Rc<Gram>,
+  pub(crate) total_mass: Rc<Gram>,
 
   /// A list to all ingredients in recipe
-  ingredients: Vec<Box<dyn Ingredient>>,
+  pub(crate) ingredients: Vec<Box<dyn Ingredient>>,
 
   /// Required recipe hydration ( Liquid / Total flour mass)
-  hydration: Hydration,
+  pub(crate) hydration: Hydration,
+
+  pub(crate) title: Option<String>,
+
+  pub(crate) notes: Option<String>,
+
+  /// Number of pieces the dough is divided to
+  pub(crate) pieces: u32,
+
+  /// How the recipe's levain is built, when its stages are given
+  pub(crate) levain: Option<LevainPlan>,
+
+  /// How long the dough is estimated to ferment, when something leavens it
+  pub(crate) fermentation: Option<Fermentation>,
+
+  /// The temperatures the water added is tempered by, to reach the desired dough temperature
+  pub(crate) ddt: Option<Ddt>,
+
+  /// The water added, tempered
+  pub(crate) tempering: Option<Tempering>,
+}
+
+/// The recipe's overall properties, shown below the ingredients
+#[derive(Debug, Serialize)]
+pub struct Properties {
+  /// The free water, hydrating the flour, as percent of the flour
+  pub hydration: ResultingHydration,
+
+  /// All the water, including the water held by soakers, as percent of the flour
+  pub total_hydration: Ratio,
+  pub expected_hydration: Hydration,
+  pub total_flour: Gram,
+  pub total_water: Gram,
+  pub total_enrichment: Gram,
+  pub total_weight: Gram,
+  pub pieces: u32,
+  pub piece_weight: Gram,
+
+  /// The allergens of the ingredients known to the catalog
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub allergens: Vec<String>,
+}
+
+/// A machine readable rendition of the recipe
+#[derive(Debug, Serialize)]
+pub struct Report {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub notes: Option<String>,
+  pub ingredients: Vec<Summary>,
+  pub properties: Properties,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub levain: Option<LevainPlan>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub fermentation: Option<Fermentation>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub water_temperature: Option<Tempering>,
 }
 
 impl Recipe {
@@ -30,10 +92,25 @@ impl Recipe {
     Recipe {
       total_mass: Rc::new(total_mass),
       ingredients: vec![],
-      hydration: hydration,
+      hydration,
+      title: None,
+      notes: None,
+      pieces: 1,
+      levain: None,
+      fermentation: None,
+      ddt: None,
+      tempering: None,
     }
   }
 
+  pub fn total_flour(&self) -> Gram {
+    *self.total_mass
+  }
+
+  pub fn ingredients(&self) -> &[Box<dyn Ingredient>] {
+    &self.ingredients
+  }
+
   pub fn other(&self) -> Gram {
     self
       .ingredients
@@ -63,101 +140,120 @@ impl Recipe {
       .fold(Gram::ZERO, |a, i| a + i.water())
   }
 
-  /// The amount of additional water required to achieve the desired hydration
+  /// The water held by soakers, not free to hydrate the flour
+  pub fn bound_water(&self) -> Gram {
+    self
+      .ingredients
+      .iter()
+      .fold(Gram::ZERO, |a, i| a + i.bound())
+  }
+
+  /// The mass of the named ingredient, flours are matched by name, preferments and water by their kind
+  pub fn mass_of(&self, name: &str) -> Option<Gram> {
+    self.ingredients.iter().find_map(|i| i.mass_of(name))
+  }
+
+  /// The amount of additional water required to achieve the desired hydration,
+  /// the water held by soakers does not hydrate the flour
   ///
-  ///         <Missing water> =  <Total flour mass> x Hydration - <current water content>
+  /// ```text
+  ///         <Missing water> =  <Total flour mass> x Hydration - (<current water content> - <bound water>)
+  /// ```
   pub fn missing_water(&self) -> Gram {
-    (*self.total_mass * self.hydration) - self.water()
+    (*self.total_mass * self.hydration) - (self.water() - self.bound_water())
+  }
+
+  /// Whether a preferment ferments along, its temperature then counts toward the dough's
+  fn has_preferment(&self) -> bool {
+    self
+      .ingredients
+      .iter()
+      .any(|i| matches!(i.leavening(), Some(Leavening::Sourdough(_) | Leavening::Preferment { .. })))
   }
 
   /// If the requested hydration is not reached
-  /// Add appropriate water
+  /// Add appropriate water, tempered when the desired dough temperature is given
   /// If the hydration is already exceeded do nothing
   pub fn add_missing_water(mut self) -> Self {
     let to_add = self.missing_water();
     if to_add > Gram::ZERO {
-      self.ingredients.push(Box::new(Water { mass: to_add }));
-    }
-    self
-  }
-
-  pub fn add_generic_enrichment(mut self, name: String, ratio: Option<f32>) -> Self {
-    if let Some(ratio) = ratio {
-      self
-        .ingredients
-        .push(Box::new(Enrichment::<SaltPercentage>::new(name, &self.total_mass, ratio.into())));
+      let water = Water::from(to_add);
+      let water = match &self.ddt {
+        Some(ddt) => {
+          let tempering = ddt.temper(to_add, self.has_preferment());
+          self.tempering = Some(tempering.clone());
+          water.tempered(tempering)
+        }
+        None => water,
+      };
+      self.ingredients.push(Box::new(water));
     }
     self
   }
 
-  pub fn extract_flour_desc(desc: &String) -> Result<(String, i32)> {
-    match desc.split_once(':') {
-      Some((name, ratio)) => Ok((name.to_owned(), ratio.parse::<i32>().unwrap())),
-      _ => Err(Error::InvalidFlourArg(desc.to_owned())),
-    }
-  }
-
-  pub fn add_flour(&mut self, flours: Vec<String>) -> Result<Flours> {
-    flours.iter().fold(
-      Ok(Flours::new(&self.total_mass)),
-      |acc: Result<Flours>, f| {
-        let (name, ratio) = Self::extract_flour_desc(f)?;
-        Ok(acc?.add_flour(name, ratio.into()))
-      },
-    )
+  /// Builds the recipe described by `document`
+  pub fn build(document: RecipeDocument) -> Result<Self> {
+    document.register()?;
+    document.builder()?.build()
   }
 
-  pub fn add_salt(self, ratio: Option<f32> ) -> Self {
-    self.add_generic_enrichment("SALT".to_string(), ratio)
-  }
-  
-  pub fn add_preferment(&mut self, preferment: Vec<String>, flours: Flours) -> Result<Flours> {
-    preferment.into_iter().fold(Ok(flours), |fs, p| {
-      match preferment::BUILDER.get(&p, &self.total_mass) {
-        Ok(preferment) => {
-          let flours = fs?.repurpose(&*preferment);
-          self.ingredients.push(preferment);
-          flours
-        }
-        _ => Err(Error::InvalidPrefermentArgs(p)),
-      }
-    })
+  /// The allergens of the ingredients and their components, as far as the catalog knows them
+  pub fn allergens(&self) -> Vec<String> {
+    let summaries: Vec<Summary> = self
+      .ingredients
+      .iter()
+      .map(|it| it.summary(*self.total_mass, self.total()))
+      .collect();
+    let names = summaries
+      .iter()
+      .flat_map(|summary| std::iter::once(summary).chain(summary.components.iter()))
+      .map(|summary| summary.name.as_str());
+    CATALOG.allergens(names)
   }
 
-  pub fn add_enrichment_from_cmd(mut self, desc: &String) -> Result<Self> {
-    if let Some((name, ratio)) = desc.split_once('%') {
-      let ratio: Ratio = ratio.parse::<i32>().unwrap().into();
-      self.ingredients.push(Box::new(Enrichment::<Ratio>::new(name.to_owned(), &self.total_mass, ratio)));
-      Ok(self)
-    } else if let Some((name, mass)) = desc.split_once(':') {
-      let mass: Gram = mass.parse::<i32>().unwrap().into();
-      self.ingredients.push(Box::new(Enrichment::<Ratio>::new_by_mass(name.to_owned(), &self.total_mass, mass)));
-      Ok(self)
-    } else  {
-     Err(Error::InvalidEnrichmentArg(desc.to_owned()))
+  pub fn properties(&self) -> Properties {
+    Properties {
+      hydration: (self.water() - self.bound_water()).as_ratio_of(&self.total_mass),
+      total_hydration: self.water().as_ratio_of(&self.total_mass),
+      expected_hydration: self.hydration,
+      total_flour: *self.total_mass,
+      total_water: self.water(),
+      total_enrichment: self.other(),
+      total_weight: self.total(),
+      pieces: self.pieces,
+      piece_weight: Gram(self.total().0 / Decimal::from(self.pieces)),
+      allergens: self.allergens(),
     }
   }
 
-  pub fn add_enrichments(self, enrichments: Vec<String>) -> Result<Self> {
-    enrichments.into_iter().fold( Ok(self), |s, desc| {
-      s?.add_enrichment_from_cmd(&desc)
-    })
+  pub fn report(&self) -> Report {
+    let total = self.total();
+    Report {
+      title: self.title.clone(),
+      notes: self.notes.clone(),
+      ingredients: self
+        .ingredients
+        .iter()
+        .map(|it| it.summary(*self.total_mass, total))
+        .collect(),
+      properties: self.properties(),
+      levain: self.levain.clone(),
+      fermentation: self.fermentation.clone(),
+      water_temperature: self.tempering.clone(),
+    }
   }
 
-  pub fn build(cli: Cli) -> Result<Self> {
-    let mut recipe = Recipe::new(cli.mass.unwrap().into(), cli.hydration.unwrap().into());
-    let flours = recipe.add_flour(cli.flour)?;
-    let flours = recipe.add_preferment(cli.preferment, flours)?;
-    recipe.ingredients.push(Box::new(flours));
-    
-    Ok(recipe.add_salt(cli.salt_percentage)
-             .add_enrichments(cli.enrichment)?
-             .add_missing_water()
-      )
+  pub fn display_json(self) -> Result<()> {
+    let json = serde_json::to_string_pretty(&self.report()).map_err(|e| Error::Serialization(e.to_string()))?;
+    println!("{}", json);
+    Ok(())
   }
 
   pub fn display(self) -> Result<()> {
     let total = self.total();
+    if let Some(title) = &self.title {
+      println!("{}", title.to_uppercase());
+    }
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
     table.add_row(row![ cbFy =>"#", "", "mass", "%Flour", "%Total", "Comment"]);
@@ -168,30 +264,185 @@ impl Recipe {
       .fold(table, |t, it| it.describe(t, total))
       .printstd();
 
-    let real_hydration: Hydration = ((self.water().0 / self.total_mass.0) * PERCENT).into();
+    let properties = self.properties();
     println!(
       "{} / {} = {}",
-      self.water(),
-      *self.total_mass,
-      real_hydration
+      properties.total_water,
+      properties.total_flour,
+      properties.total_hydration
     );
 
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_CLEAN);
 
     println!("PROPERTIES");
-    if real_hydration == self.hydration {
-      table.add_row(row!["", "HYDRATION", r -> real_hydration]);
+    let hydration = if self.bound_water() == Gram::ZERO {
+      "HYDRATION"
+    } else {
+      table.add_row(row!["", "TOTAL HYDRATION", r -> properties.total_hydration]);
+      "EFFECTIVE HYDRATION"
+    };
+    if properties.hydration.as_decimal() == properties.expected_hydration.as_decimal() {
+      table.add_row(row!["", hydration, r -> properties.hydration]);
     } else {
-      table.add_row(row!["", Fr -> "HYDRATION", rFr -> real_hydration, Fr -> "EXPECTED HYDRATION:", Frr -> self.hydration]);
+      table.add_row(row!["", Fr -> hydration, rFr -> properties.hydration, Fr -> "EXPECTED HYDRATION:", Frr -> properties.expected_hydration]);
+    }
+    table.add_row(row!["", "TOTAL FLOUR", r-> properties.total_flour ]);
+    table.add_row(row!["", "TOTAL WATER", r-> properties.total_water]);
+    table.add_row(row!["", "TOTAL ENRICHMENT*", r-> properties.total_enrichment]);
+    table.add_row(row!["", "TOTAL WEIGHT", r-> properties.total_weight]);
+    if properties.pieces > 1 {
+      table.add_row(row!["", "PIECES", r-> format!("{} x {}", properties.pieces, properties.piece_weight)]);
+    }
+    if !properties.allergens.is_empty() {
+      table.add_row(row!["", "ALLERGENS", r-> properties.allergens.join(", ")]);
     }
-    table.add_row(row!["", "TOTAL FLOUR", r-> self.total_mass ]);
-    table.add_row(row!["", "TOTAL WATER", r-> self.water()]);
-    table.add_row(row!["", "TOTAL ENRICHMENT*", r-> self.other()]);
-    table.add_row(row!["", "TOTAL WEIGHT", r-> total]);
     table.printstd();
 
     println!("* Enrichement refers to seeds, sugar, butter, etc. While not conservative salt is also counted here");
+    if let Some(fermentation) = &self.fermentation {
+      println!();
+      fermentation.display();
+    }
+    if let Some(levain) = &self.levain {
+      println!();
+      levain.display();
+    }
+    if let Some(notes) = &self.notes {
+      println!("\nNOTES\n{}", notes);
+    }
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::diagnostics;
+
+  fn document() -> RecipeDocument {
+    RecipeDocument {
+      mass: 1000,
+      flours: vec!["white:80".to_owned(), "rye:20".to_owned()],
+      preferments: vec!["starter:10:100".to_owned()],
+      enrichments: vec!["butter%5".to_owned()],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn dough_weight_solves_total_flour() {
+    let document = RecipeDocument {
+      dough: Some(900.0),
+      pieces: Some(3),
+      enrichments: vec!["butter%5".to_owned(), "seeds:50".to_owned()],
+      ..document()
+    };
+
+    let recipe = Recipe::build(document).unwrap();
+    let properties = recipe.properties();
+    assert!((properties.total_weight - Gram::from(2700)).0.abs() <= Decimal::ONE);
+    assert_eq!(properties.pieces, 3);
+    assert_eq!(properties.piece_weight, properties.total_weight / 3);
+  }
+
+  #[test]
+  fn many_pieces_weigh_little() {
+    let recipe = Recipe::build(RecipeDocument {
+      pieces: Some(u32::MAX),
+      ..document()
+    })
+    .unwrap();
+    let properties = recipe.properties();
+    assert!(properties.piece_weight > Gram::ZERO);
+    assert!(properties.piece_weight < Gram::from(0.01));
+  }
+
+  #[test]
+  fn many_pieces_weigh_a_lot() {
+    let recipe = Recipe::build(RecipeDocument {
+      dough: Some(500.0),
+      pieces: Some(u32::MAX),
+      ..document()
+    })
+    .unwrap();
+    let properties = recipe.properties();
+    let total_weight = Gram(Decimal::from(500) * Decimal::from(u32::MAX));
+    assert!((properties.total_weight - total_weight).0.abs() <= Decimal::ONE);
+    assert!((properties.piece_weight - Gram::from(500)).0.abs() < Decimal::ONE);
+  }
+
+  #[test]
+  fn unreachable_dough_weight_is_an_error() {
+    let document = RecipeDocument {
+      dough: Some(10.0),
+      enrichments: vec!["seeds:50".to_owned()],
+      ..document()
+    };
+    assert_eq!(Recipe::build(document).unwrap_err(), Error::UnreachableTarget(10.into()));
+  }
+
+  #[test]
+  fn anchor_preserves_the_percentages() {
+    let anchored = |anchor: &str| {
+      Recipe::build(RecipeDocument {
+        anchor: Some(anchor.to_owned()),
+        ..document()
+      })
+      .unwrap()
+    };
+
+    let recipe = anchored("starter:180");
+    assert_eq!(recipe.mass_of("starter"), Some(180.into()));
+    assert_eq!(*recipe.total_mass, 900.into());
+    assert_eq!(recipe.properties().hydration.as_decimal(), recipe.properties().expected_hydration.as_decimal());
+
+    let recipe = anchored("RYE:1000");
+    assert_eq!(recipe.mass_of("rye"), Some(1000.into()));
+    assert_eq!(*recipe.total_mass, 5000.into());
+
+    // The water added makes up for the butter's water, 16% of its mass rounded to whole grams
+    let recipe = anchored("water:300");
+    let water = recipe.mass_of("water").unwrap();
+    assert!((water - Gram::from(300)).0.abs() <= Decimal::new(1, 2));
+  }
+
+  #[test]
+  fn anchor_on_a_missing_ingredient_is_an_error() {
+    let document = RecipeDocument {
+      anchor: Some("spelt:100".to_owned()),
+      ..document()
+    };
+    assert_eq!(Recipe::build(document).unwrap_err(), Error::UnknownIngredient("spelt".to_owned()));
+  }
+
+  #[test]
+  fn anchor_out_of_range_enrichment_is_located() {
+    let document = RecipeDocument {
+      flours: vec!["white:100".to_owned()],
+      preferments: vec![],
+      enrichments: vec!["milk:2000000".to_owned()],
+      anchor: Some("white:0.01".to_owned()),
+      ..document()
+    };
+    let error = diagnostics(Recipe::build(document).unwrap_err());
+    assert_eq!((error[0].field.as_str(), error[0].span.clone()), ("mass", 5..12));
+  }
+
+  #[test]
+  fn report_adds_up_to_the_recipe() {
+    let recipe = Recipe::build(document()).unwrap();
+    let report = recipe.report();
+
+    let total = report.ingredients.iter().fold(Gram::ZERO, |a, i| a + i.total);
+    assert_eq!(total, report.properties.total_weight);
+    assert_eq!(report.properties.total_flour, 1000.into());
+    assert_eq!(report.properties.hydration.as_decimal(), report.properties.expected_hydration.as_decimal());
+
+    assert_eq!(report.properties.allergens, vec!["gluten", "milk", "wheat"]);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["properties"]["total_water"], 700.0);
+    assert_eq!(json["ingredients"][0]["components"][1]["water"], 100.0);
+  }
+}
@@ -0,0 +1,295 @@
+use crate::common::mass::*;
+use crate::common::Description;
+use crate::error::collect;
+use crate::ingredient::enrichment::water_content;
+use crate::ingredient::preferment::HydrationPercent;
+use crate::{Error, Result};
+use prettytable::{format, row, Table};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// A recipe written down in grams, i.e. the masses actually weighed, rather than in baker's percentages.
+///
+/// It is the inverse of `Recipe::build`, given the masses it derives the total flour (including the flour
+/// in preferments), the true hydration (including the water in preferments and enrichments) and the percentages of all
+/// ingredients relative to the total flour.
+#[derive(Debug, Clone, Default)]
+pub struct Weighed {
+  pub flours: Vec<(String, Gram)>,
+  pub water: Gram,
+  pub preferments: Vec<(String, Gram, HydrationPercent)>,
+  pub salt: Gram,
+  pub enrichments: Vec<(String, Gram)>,
+}
+
+/// An ingredient's mass and its percent of the total flour
+#[derive(Debug, Clone, Serialize)]
+pub struct Share {
+  pub name: String,
+  pub mass: Gram,
+  pub percent_flour: Ratio,
+}
+
+/// A weighed preferment broken down to its flour and water
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefermentShare {
+  pub name: String,
+  pub mass: Gram,
+  pub flour: Gram,
+  pub water: Gram,
+  pub hydration: HydrationPercent,
+
+  /// The preferment's flour as percent of the total flour
+  pub portion: Ratio,
+}
+
+/// The baker's percentages derived from a `Weighed` recipe
+#[derive(Debug, Clone, Serialize)]
+pub struct Formula {
+  pub flours: Vec<Share>,
+  pub preferments: Vec<PrefermentShare>,
+  pub water: Share,
+  pub salt: Share,
+  pub enrichments: Vec<Share>,
+  pub total_flour: Gram,
+  pub total_water: Gram,
+  pub hydration: Ratio,
+  pub total_weight: Gram,
+}
+
+impl Weighed {
+  /// Parses a `<name>:<mass>` description
+  fn extract_mass_desc(kind: &'static str, desc: &str, example: &str) -> Result<(String, Gram)> {
+    let description = Description::new(kind, desc, "<name>:<mass>", example);
+    let (name, mass) = description.whole(kind).split_once(':', "name", "mass")?;
+    Ok((name.name()?, mass.mass()?))
+  }
+
+  pub fn add_flour(mut self, desc: &str) -> Result<Self> {
+    let flour = Self::extract_mass_desc("flour", desc, "white:500")?;
+    self.flours.push(flour);
+    Ok(self)
+  }
+
+  /// Adds a preferment described as `<name>:<mass>:<hydration>`
+  pub fn add_preferment(mut self, desc: &str) -> Result<Self> {
+    let description = Description::new("preferment", desc, "<name>:<mass>:<hydration>", "starter:100:100");
+    let fields = description
+      .whole("preferment")
+      .split(':', &["name", "mass", "hydration"], 3)?;
+    self.preferments.push((fields[0].name()?, fields[1].mass()?, fields[2].percent()?));
+    Ok(self)
+  }
+
+  pub fn add_enrichment(mut self, desc: &str) -> Result<Self> {
+    let enrichment = Self::extract_mass_desc("enrichment", desc, "seeds:50")?;
+    self.enrichments.push(enrichment);
+    Ok(self)
+  }
+
+  /// Splits a preferment's mass into its (flour, water)
+  ///
+  /// ```text
+  ///   <flour> = <mass> / (1 + <hydration>)
+  /// ```
+  fn split(mass: Gram, hydration: HydrationPercent) -> (Gram, Gram) {
+    let flour = mass / (Decimal::ONE + hydration.as_decimal());
+    (flour, mass - flour)
+  }
+
+  pub fn total_flour(&self) -> Gram {
+    let preferment_flour = self
+      .preferments
+      .iter()
+      .fold(Gram::ZERO, |a, (_, mass, hydration)| a + Self::split(*mass, *hydration).0);
+    self.flours.iter().fold(preferment_flour, |a, (_, mass)| a + *mass)
+  }
+
+  pub fn formula(&self) -> Result<Formula> {
+    let total_flour = self.total_flour();
+    if total_flour == Gram::ZERO {
+      return Err(Error::NoFlour);
+    }
+    // A percentage out of range points at the mass, as described to `weigh`
+    let share = |kind: &'static str, name: &str, mass: Gram| -> Result<Share> {
+      let desc = format!("{}:{}", name, mass.0.normalize());
+      let description = Description::new(kind, &desc, "<name>:<mass>", "seeds:50");
+      let (_, field) = description.whole(kind).split_once(':', "name", "mass")?;
+      Ok(Share {
+        name: name.to_owned(),
+        mass,
+        percent_flour: field.ratio_of(mass, total_flour)?,
+      })
+    };
+    let amount = |kind: &'static str, mass: Gram, example: &str| -> Result<Share> {
+      let description = Description::new(kind, &mass.0.normalize().to_string(), "<gram>", example);
+      Ok(Share {
+        name: kind.to_owned(),
+        mass,
+        percent_flour: description.whole(kind).ratio_of(mass, total_flour)?,
+      })
+    };
+
+    let preferments: Vec<PrefermentShare> = self
+      .preferments
+      .iter()
+      .map(|(name, mass, hydration)| {
+        let (flour, water) = Self::split(*mass, *hydration);
+        PrefermentShare {
+          name: name.clone(),
+          mass: *mass,
+          flour,
+          water,
+          hydration: *hydration,
+          portion: flour.as_ratio_of(&total_flour),
+        }
+      })
+      .collect();
+
+    let mut errors = vec![];
+    let flours: Vec<Share> = self
+      .flours
+      .iter()
+      .filter_map(|(name, mass)| collect(&mut errors, share("flour", name, *mass)))
+      .collect();
+    let enrichments: Vec<Share> = self
+      .enrichments
+      .iter()
+      .filter_map(|(name, mass)| collect(&mut errors, share("enrichment", name, *mass)))
+      .collect();
+    let (water_share, salt) = (amount("water", self.water, "350"), amount("salt", self.salt, "10"));
+    errors.extend([&water_share, &salt].into_iter().filter_map(|share| share.clone().err()));
+    Error::merge(errors)?;
+
+    let water = preferments.iter().fold(self.water, |a, p| a + p.water);
+    let total_weight = enrichments
+      .iter()
+      .fold(total_flour + water + self.salt, |a, e| a + e.mass);
+
+    // The water in enrichments, e.g. milk, counts toward the hydration
+    let total_water = enrichments.iter().fold(water, |a, e| match water_content(&e.name) {
+      Some(content) => a + e.mass * content,
+      None => a,
+    });
+
+    Ok(Formula {
+      flours,
+      preferments,
+      water: water_share?,
+      salt: salt?,
+      enrichments,
+      total_flour,
+      total_water,
+      hydration: amount("water", total_water, "350")?.percent_flour,
+      total_weight,
+    })
+  }
+}
+
+impl Formula {
+  pub fn display(self) -> Result<()> {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.add_row(row![ cbFy =>"#", "", "mass", "%Flour", "Comment"]);
+
+    let added_flour = self.flours.iter().fold(Gram::ZERO, |a, f| a + f.mass);
+    let added_ratio: Ratio = added_flour.as_ratio_of(&self.total_flour);
+    table.add_row(row![ b -> "FLOUR", "", rb -> added_flour, rb -> added_ratio, b -> "Added to the final dough"]);
+    for flour in &self.flours {
+      table.add_row(row!["", flour.name, r -> flour.mass, r -> flour.percent_flour]);
+    }
+
+    for preferment in &self.preferments {
+      let comment = format!("{} Hydration, {} of total flour", preferment.hydration, preferment.portion);
+      let water_ratio: Ratio = preferment.water.as_ratio_of(&self.total_flour);
+      table.add_row(row![lb -> preferment.name.to_uppercase(), "", rb -> preferment.mass, cb -> "", b -> comment]);
+      table.add_row(row!["", l -> "flour", r -> preferment.flour, r -> preferment.portion]);
+      table.add_row(row!["", l -> "water", r -> preferment.water, r -> water_ratio]);
+    }
+
+    table.add_row(row![ lb -> "WATER", "", rb -> self.water.mass, rb -> self.water.percent_flour]);
+    table.add_row(row![ lb -> "SALT", "", rb -> self.salt.mass, rb -> self.salt.percent_flour]);
+    for enrichment in &self.enrichments {
+      table.add_row(
+        row![ b -> enrichment.name.to_uppercase(), "", br -> enrichment.mass, br -> enrichment.percent_flour],
+      );
+    }
+    table.printstd();
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+
+    println!("PROPERTIES");
+    table.add_row(row!["", "HYDRATION", r -> self.hydration]);
+    table.add_row(row!["", "TOTAL FLOUR", r -> self.total_flour]);
+    table.add_row(row!["", "TOTAL WATER", r -> self.total_water]);
+    table.add_row(row!["", "TOTAL WEIGHT", r -> self.total_weight]);
+    table.printstd();
+    Ok(())
+  }
+
+  pub fn display_json(self) -> Result<()> {
+    let json = serde_json::to_string_pretty(&self).map_err(|e| Error::Serialization(e.to_string()))?;
+    println!("{}", json);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::common::Percent;
+  use crate::error::diagnostics;
+
+  #[test]
+  fn formula_includes_preferment_flour_and_water() {
+    let weighed = Weighed::default()
+      .add_flour("white:500")
+      .unwrap()
+      .add_preferment("starter:100:100")
+      .unwrap();
+    let weighed = Weighed {
+      water: 350.into(),
+      salt: 11.into(),
+      ..weighed
+    };
+
+    let formula = weighed.formula().unwrap();
+    assert_eq!(formula.total_flour, 550.into());
+    assert_eq!(formula.total_water, 400.into());
+    assert_eq!(formula.hydration, Percent(7272));
+    assert_eq!(formula.preferments[0].portion, Percent(909));
+    assert_eq!(formula.salt.percent_flour, 2.try_into().unwrap());
+    assert_eq!(formula.total_weight, 961.into());
+  }
+
+  #[test]
+  fn water_in_enrichments_counts_toward_hydration() {
+    let weighed = Weighed::default().add_flour("white:1000").unwrap().add_enrichment("milk:200").unwrap();
+    let formula = Weighed { water: 500.into(), ..weighed }.formula().unwrap();
+    assert_eq!(formula.total_water, 674.into());
+    assert_eq!(formula.total_weight, 1700.into());
+  }
+
+  #[test]
+  fn invalid_descriptions_are_errors() {
+    assert!(Weighed::default().add_flour("white").is_err());
+    assert!(Weighed::default().add_flour("white:abc").is_err());
+    assert!(Weighed::default().add_preferment("starter:100").is_err());
+    assert!(Weighed::default().add_preferment("starter:100:10").is_err());
+    assert!(Weighed::default().add_enrichment("butter%5").is_err());
+  }
+
+  #[test]
+  fn out_of_range_percentages_are_errors() {
+    let weighed = Weighed::default()
+      .add_flour("white:0.01")
+      .unwrap()
+      .add_enrichment("milk:99999999999")
+      .unwrap();
+    let error = diagnostics(Weighed { salt: 100000.into(), ..weighed }.formula().unwrap_err());
+    assert_eq!(error.len(), 2);
+    assert_eq!((error[0].kind.as_str(), error[0].span.clone()), ("enrichment", 5..16));
+    assert_eq!(error[1].kind, "salt");
+  }
+}